@@ -1,22 +1,32 @@
 use crate::{
-    ctype::{CTypeKind, CTypeRef, TypedObject},
+    ctype::{CType, CTypeKind, CTypeRef, TypedObject},
     escape::escape,
 };
-use std::{collections::HashMap, io::Write};
+use std::{collections::HashMap, io::Write, rc::Rc};
 
 use crate::{
     ctype::{TypedNode, TypedNodeKind},
-    parser::BinOp,
+    parser::{BinOp, InitData},
 };
 
 pub struct Codegen<'src> {
-    locals: HashMap<&'src str, i32>,
+    locals: HashMap<usize, i32>,
     count: usize,
     current_fn_name: Option<&'src str>,
+    // 16 バイトを超える構造体を返す関数で、戻り値の書き込み先のアドレスを退避しておく場所
+    ret_buf_offset: Option<i32>,
+    // 可変長引数の関数で、最初の可変長部分の引数が退避されている場所
+    va_gp_offset: Option<i32>,
+    // break, continue で飛ぶ先のラベルの番号。一番内側の文が末尾
+    brk_labels: Vec<usize>,
+    cont_labels: Vec<usize>,
+    // case, default のラベルが属する switch 文の番号
+    switch_labels: Vec<usize>,
     writer: Box<dyn Write>,
 }
 
 const ARG_REG: &[&str] = &["a0", "a1", "a2", "a3", "a4", "a5", "a6", "a7"];
+const FP_ARG_REG: &[&str] = &["fa0", "fa1", "fa2", "fa3", "fa4", "fa5", "fa6", "fa7"];
 
 impl<'src> Codegen<'src> {
     pub fn new(writer: Box<dyn Write>) -> Self {
@@ -24,18 +34,58 @@ impl<'src> Codegen<'src> {
             locals: HashMap::new(),
             count: 0,
             current_fn_name: None,
+            ret_buf_offset: None,
+            va_gp_offset: None,
+            brk_labels: vec![],
+            cont_labels: vec![],
+            switch_labels: vec![],
             writer,
         }
     }
 
     fn emit_data(&mut self, program: &[TypedObject<'src>]) {
         for function in program {
-            if let TypedObject::Object { name, ctype, .. } = function {
-                writeln!(&mut self.writer, "  .global {name}").unwrap();
+            if let TypedObject::Object {
+                ctype,
+                is_static,
+                is_definition: true,
+                init_data,
+                ..
+            } = function
+            {
+                let name = function.label().unwrap();
+                if *is_static {
+                    writeln!(&mut self.writer, "  .local {name}").unwrap();
+                } else {
+                    writeln!(&mut self.writer, "  .global {name}").unwrap();
+                }
                 writeln!(&mut self.writer, "  .section .data").unwrap();
+                writeln!(&mut self.writer, "  .balign {}", ctype.borrow().align).unwrap();
                 writeln!(&mut self.writer, "{name}:").unwrap();
 
-                writeln!(&mut self.writer, "  .zero {}", ctype.borrow().size).unwrap();
+                let Some(init_data) = init_data else {
+                    writeln!(&mut self.writer, "  .zero {}", ctype.borrow().size).unwrap();
+                    continue;
+                };
+                for data in init_data {
+                    match data {
+                        InitData::Zero(size) => {
+                            writeln!(&mut self.writer, "  .zero {size}").unwrap();
+                        }
+                        InitData::Int { size, value } => {
+                            let directive = match size {
+                                1 => "byte",
+                                2 => "half",
+                                4 => "word",
+                                _ => "quad",
+                            };
+                            writeln!(&mut self.writer, "  .{directive} {value}").unwrap();
+                        }
+                        InitData::Label { label, addend } => {
+                            writeln!(&mut self.writer, "  .quad {label}{addend:+}").unwrap();
+                        }
+                    }
+                }
             }
 
             if let TypedObject::StringLiteral { id, string, .. } = function {
@@ -60,29 +110,58 @@ impl<'src> Codegen<'src> {
         for function in functions {
             if let TypedObject::Function {
                 name,
+                is_static,
                 node: Some(node),
                 params,
                 locals,
+                ret_type,
+                is_variadic,
             } = function
             {
                 let mut offset = 0;
                 for local in locals.iter().rev() {
-                    if let TypedObject::Object { name, ctype, .. } = local {
+                    if let TypedObject::Object {
+                        id,
+                        ctype,
+                        is_static: false,
+                        ..
+                    } = local
+                    {
                         let ty = ctype.borrow();
                         offset = align_to(offset, ty.align);
                         offset += ty.size;
-                        self.locals.insert(name, -(offset as i32));
+                        self.locals.insert(*id, -(offset as i32));
                     }
                 }
+                let ret_by_ref = is_large_struct(&ret_type.borrow());
+                self.ret_buf_offset = if ret_by_ref {
+                    offset = align_to(offset, 8) + 8;
+                    Some(-(offset as i32))
+                } else {
+                    None
+                };
                 let stack_size = align_to(offset, 16);
 
                 self.current_fn_name = Some(name);
 
                 writeln!(&mut self.writer, "  .section .text").unwrap();
-                writeln!(&mut self.writer, "  .global {name}").unwrap();
+                if is_static {
+                    writeln!(&mut self.writer, "  .local {name}").unwrap();
+                } else {
+                    writeln!(&mut self.writer, "  .global {name}").unwrap();
+                }
                 writeln!(&mut self.writer, "{name}:").unwrap();
 
                 // Prologue
+                // 可変長引数の関数では、呼び出し側がスタックに置いた引数のすぐ下に a0-a7 を退避する。
+                // こうすると va_arg はレジスタで渡された引数からスタックで渡された引数へそのまま進める
+                let va_area_size = if is_variadic { 8 * ARG_REG.len() } else { 0 };
+                if is_variadic {
+                    writeln!(&mut self.writer, "  addi sp, sp, -{va_area_size}").unwrap();
+                    for (i, reg) in ARG_REG.iter().enumerate() {
+                        writeln!(&mut self.writer, "  sd {reg}, {}(sp)", 8 * i).unwrap();
+                    }
+                }
                 push(&mut self.writer, "ra");
                 push(&mut self.writer, "fp");
                 writeln!(&mut self.writer, "  mv fp, sp").unwrap();
@@ -90,25 +169,70 @@ impl<'src> Codegen<'src> {
                 // RISC-V における即値の範囲は [-2048, 2047] なので、それを超える場合には addi をその分繰り返す
                 addi(&mut self.writer, "sp", "sp", 0 - (stack_size as i32));
 
-                for (param, reg) in params.iter().zip(ARG_REG) {
-                    let offset = self.locals.get(param.name().unwrap()).unwrap();
+                if let Some(offset) = self.ret_buf_offset {
+                    writeln!(&mut self.writer, "  sd a0, {offset}(fp)").unwrap();
+                }
 
-                    if let TypedObject::Object { ctype, .. } = param {
-                        let size = ctype.borrow().size;
-                        match size {
-                            1 => {
-                                writeln!(&mut self.writer, "  sb {reg}, {offset}(fp)").unwrap();
-                            }
-                            4 => {
-                                writeln!(&mut self.writer, "  sw {reg}, {offset}(fp)").unwrap();
+                let param_tys = params
+                    .iter()
+                    .filter_map(|param| match param {
+                        TypedObject::Object { ctype, .. } => Some(Rc::clone(ctype)),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                let arg_locs = assign_arg_regs(&param_tys, None, usize::from(ret_by_ref));
+
+                // スタックで渡された引数は、呼び出し時の sp (fp + 16 + 退避領域) から 8 バイトずつ並んでいる
+                let store_param = |writer: &mut Box<dyn Write>, slot, offset, size| match slot {
+                    Slot::Reg(reg) => store_part(writer, reg, "fp", offset, size),
+                    Slot::Stack(n) => {
+                        writeln!(writer, "  ld t0, {}(fp)", 16 + va_area_size + 8 * n).unwrap();
+                        store_part(writer, "t0", "fp", offset, size);
+                    }
+                };
+                for (param, loc) in params.iter().zip(&arg_locs) {
+                    if let TypedObject::Object { id, ctype, .. } = param {
+                        let offset = self.locals[id];
+                        let ty = ctype.borrow();
+                        match loc {
+                            ArgLoc::Reg(_) | ArgLoc::Stack(_) => {
+                                let size = if ty.is_struct_or_union() { 8 } else { ty.size };
+                                store_param(&mut self.writer, loc.slots()[0], offset, size);
                             }
-                            _ => {
-                                writeln!(&mut self.writer, "  sd {reg}, {offset}(fp)").unwrap();
+                            ArgLoc::Struct(parts) => {
+                                for part in parts {
+                                    let offset = offset + part.offset as i32;
+                                    store_param(&mut self.writer, part.slot, offset, part.size);
+                                }
                             }
                         }
                     }
                 }
 
+                // va_start は、退避した a0-a7 (fp + 16) のうち名前付きの引数が使った分の後ろを指す
+                self.va_gp_offset = is_variadic.then(|| {
+                    let gp = usize::from(ret_by_ref)
+                        + arg_locs
+                            .iter()
+                            .flat_map(ArgLoc::slots)
+                            .filter(|&slot| !is_fp_slot(slot))
+                            .count();
+                    16 + 8 * gp as i32
+                });
+
+                // 参照渡しされた構造体は、アドレスを一旦置いた場所に中身をコピーする
+                for (param, loc) in params.iter().zip(&arg_locs) {
+                    if let TypedObject::Object { id, ctype, .. } = param
+                        && matches!(loc, ArgLoc::Reg(_) | ArgLoc::Stack(_))
+                        && ctype.borrow().is_struct_or_union()
+                    {
+                        let offset = self.locals[id];
+                        writeln!(&mut self.writer, "  ld a0, {offset}(fp)").unwrap();
+                        addi(&mut self.writer, "a1", "fp", offset);
+                        copy_struct(&mut self.writer, &ctype.borrow());
+                    }
+                }
+
                 self.gen_stmt(node);
 
                 // Epilogue
@@ -116,6 +240,9 @@ impl<'src> Codegen<'src> {
                 writeln!(&mut self.writer, "  mv sp, fp").unwrap();
                 pop(&mut self.writer, "fp");
                 pop(&mut self.writer, "ra");
+                if is_variadic {
+                    writeln!(&mut self.writer, "  addi sp, sp, {va_area_size}").unwrap();
+                }
 
                 writeln!(&mut self.writer, "  ret").unwrap();
 
@@ -125,22 +252,36 @@ impl<'src> Codegen<'src> {
     }
 
     fn gen_addr(&mut self, node: TypedNode) {
+        // 構造体を返す関数呼び出しの値は、戻り値を置いた一時領域のアドレスになっている
+        if matches!(node.kind, TypedNodeKind::FuncCall { .. })
+            && node.ctype.as_ref().unwrap().borrow().is_struct_or_union()
+        {
+            self.gen_expr(node);
+            return;
+        }
+
+        // 構造体の代入式は、代入した後の左辺のアドレスになる。store は左辺のアドレスを a1 に残す
+        if matches!(
+            node.kind,
+            TypedNodeKind::BinOp {
+                op: BinOp::Assign,
+                ..
+            }
+        ) && node.ctype.as_ref().unwrap().borrow().is_struct_or_union()
+        {
+            self.gen_expr(node);
+            writeln!(&mut self.writer, "  mv a0, a1").unwrap();
+            return;
+        }
+
         match node.kind {
-            TypedNodeKind::Var(object) => match *object {
-                TypedObject::Object { name, is_local, .. } => {
-                    if is_local {
-                        addi(
-                            &mut self.writer,
-                            "a0",
-                            "fp",
-                            *self.locals.get(name).unwrap(),
-                        );
-                    } else {
-                        writeln!(&mut self.writer, "  la a0, {name}").unwrap();
-                    }
+            TypedNodeKind::Var(object) => match (object.label(), *object) {
+                // グローバル変数、関数内の static 変数、文字列リテラル
+                (Some(label), _) => {
+                    writeln!(&mut self.writer, "  la a0, {label}").unwrap();
                 }
-                TypedObject::StringLiteral { id, .. } => {
-                    writeln!(&mut self.writer, "  la a0, .L..{id}").unwrap();
+                (None, TypedObject::Object { id, .. }) => {
+                    addi(&mut self.writer, "a0", "fp", *self.locals.get(&id).unwrap());
                 }
                 _ => panic!(
                     "object.kind is not TypedObjectKind::Object or TypedObjectKind::StringLiteral"
@@ -157,6 +298,21 @@ impl<'src> Codegen<'src> {
                 self.gen_expr(*lhs);
                 self.gen_addr(*rhs);
             }
+            // 構造体の条件演算子は、選ばれた方の値のアドレスになる
+            TypedNodeKind::Cond { cond, then, els } => {
+                self.count += 1;
+                let count = self.count;
+
+                let cond_ty = cond.ctype.clone().unwrap();
+                self.gen_expr(*cond);
+                cmp_zero(&mut self.writer, &cond_ty.borrow());
+                writeln!(&mut self.writer, "  beq a0, zero, .L.else.{count}").unwrap();
+                self.gen_addr(*then);
+                writeln!(&mut self.writer, "  j .L.end.{count}").unwrap();
+                writeln!(&mut self.writer, ".L.else.{count}:").unwrap();
+                self.gen_addr(*els);
+                writeln!(&mut self.writer, ".L.end.{count}:").unwrap();
+            }
             TypedNodeKind::Member { node, member } => {
                 self.gen_addr(*node);
                 writeln!(self.writer, "  addi a0, a0, {}", member.offset).unwrap();
@@ -173,6 +329,10 @@ impl<'src> Codegen<'src> {
             TypedNodeKind::Num(value) => {
                 writeln!(&mut self.writer, "  li a0, {value}").unwrap();
             }
+            TypedNodeKind::FNum(value) => {
+                writeln!(&mut self.writer, "  li t0, {}", value.to_bits() as i64).unwrap();
+                writeln!(&mut self.writer, "  fmv.d.x fa0, t0").unwrap();
+            }
             TypedNodeKind::Var(_) | TypedNodeKind::Member { .. } => {
                 self.gen_addr(node);
                 load(&mut self.writer, &ctype);
@@ -184,19 +344,149 @@ impl<'src> Codegen<'src> {
             TypedNodeKind::Addr(node) => {
                 self.gen_addr(*node);
             }
-            TypedNodeKind::FuncCall { name, args } => {
-                let mut nargs = 0;
-                for arg in args.into_iter().rev() {
+            TypedNodeKind::Cond { cond, then, els } => {
+                self.count += 1;
+                let count = self.count;
+
+                let cond_ty = cond.ctype.clone().unwrap();
+                self.gen_expr(*cond);
+                cmp_zero(&mut self.writer, &cond_ty.borrow());
+                writeln!(&mut self.writer, "  beq a0, zero, .L.else.{count}").unwrap();
+                self.gen_expr(*then);
+                writeln!(&mut self.writer, "  j .L.end.{count}").unwrap();
+                writeln!(&mut self.writer, ".L.else.{count}:").unwrap();
+                self.gen_expr(*els);
+                writeln!(&mut self.writer, ".L.end.{count}:").unwrap();
+            }
+            TypedNodeKind::Cast(node) => {
+                let from = node.ctype.clone().unwrap();
+                self.gen_expr(*node);
+                cast(&mut self.writer, &from, &ctype);
+            }
+            TypedNodeKind::VaStart(ap) => {
+                self.gen_addr(*ap);
+                addi(&mut self.writer, "t1", "fp", self.va_gp_offset.unwrap());
+                writeln!(&mut self.writer, "  sd t1, 0(a0)").unwrap();
+            }
+            TypedNodeKind::MemZero(var) => {
+                let size = var.ctype.as_ref().unwrap().borrow().size;
+                self.gen_addr(*var);
+                if size > 0 {
+                    self.count += 1;
+                    let count = self.count;
+                    writeln!(&mut self.writer, "  li t0, {size}").unwrap();
+                    writeln!(&mut self.writer, ".L.memzero.{count}:").unwrap();
+                    writeln!(&mut self.writer, "  sb zero, 0(a0)").unwrap();
+                    writeln!(&mut self.writer, "  addi a0, a0, 1").unwrap();
+                    writeln!(&mut self.writer, "  addi t0, t0, -1").unwrap();
+                    writeln!(&mut self.writer, "  bnez t0, .L.memzero.{count}").unwrap();
+                }
+            }
+            TypedNodeKind::FuncCall {
+                name,
+                args,
+                variadic_from,
+                ret_buf,
+            } => {
+                let arg_tys = args
+                    .iter()
+                    .map(|arg| arg.ctype.clone().unwrap())
+                    .collect::<Vec<_>>();
+                // 16 バイトを超える構造体を返す関数には、戻り値の書き込み先のアドレスを a0 で渡す
+                let ret_by_ref = is_large_struct(&ctype.borrow());
+                let arg_locs = assign_arg_regs(&arg_tys, variadic_from, usize::from(ret_by_ref));
+
+                // スタックで渡す引数の領域を先に確保しておき、sp を 16 バイト境界に揃えたまま呼び出す
+                let slots = arg_locs.iter().flat_map(ArgLoc::slots).collect::<Vec<_>>();
+                let stack_args = slots
+                    .iter()
+                    .filter(|slot| matches!(slot, Slot::Stack(_)))
+                    .count();
+                let stack_args_size = align_to(8 * stack_args, 16) as i32;
+                if stack_args_size > 0 {
+                    addi(&mut self.writer, "sp", "sp", -stack_args_size);
+                }
+
+                for (arg, loc) in args.into_iter().zip(&arg_locs).rev() {
+                    let arg_ty = arg.ctype.clone().unwrap();
                     self.gen_expr(arg);
-                    push(&mut self.writer, "a0");
-                    nargs += 1;
+
+                    match loc {
+                        // 構造体のメンバを読み込んで、レジスタに取り出す順番と逆順に積む
+                        ArgLoc::Struct(parts) => {
+                            for part in parts.iter().rev() {
+                                if is_fp_slot(part.slot) {
+                                    load_part(&mut self.writer, "ft0", "a0", 0, part);
+                                    push_f(&mut self.writer, "ft0");
+                                } else {
+                                    load_part(&mut self.writer, "t1", "a0", 0, part);
+                                    push(&mut self.writer, "t1");
+                                }
+                            }
+                        }
+                        ArgLoc::Reg(_) | ArgLoc::Stack(_) if arg_ty.borrow().is_flonum() => {
+                            push_f(&mut self.writer, "fa0");
+                        }
+                        ArgLoc::Reg(_) | ArgLoc::Stack(_) => {
+                            // psABI では 32 bit の値は符号の有無に関わらず符号拡張して渡す
+                            if is_uint(&arg_ty) {
+                                writeln!(&mut self.writer, "  sext.w a0, a0").unwrap();
+                            }
+                            push(&mut self.writer, "a0");
+                        }
+                    }
                 }
 
-                for reg in ARG_REG.iter().take(nargs) {
-                    pop(&mut self.writer, reg);
+                // 浮動小数点数は fa0-fa7 に入れるが、可変長部分の引数と
+                // fa0-fa7 から溢れた引数は整数レジスタにビット列のまま入れる。
+                // スタックに置く引数は、まだ積まれている値の上にある確保済みの領域に書き込む
+                for (i, slot) in slots.iter().enumerate() {
+                    match *slot {
+                        Slot::Reg(reg) if is_fp_reg(reg) => pop_f(&mut self.writer, reg),
+                        Slot::Reg(reg) => pop(&mut self.writer, reg),
+                        Slot::Stack(n) => {
+                            pop(&mut self.writer, "t0");
+                            let offset = 8 * (slots.len() - i - 1 + n);
+                            writeln!(&mut self.writer, "  sd t0, {offset}(sp)").unwrap();
+                        }
+                    }
+                }
+
+                let ret_buf_offset = ret_buf.map(|obj| match *obj {
+                    TypedObject::Object { id, .. } => self.locals[&id],
+                    _ => unreachable!(),
+                });
+                if ret_by_ref {
+                    addi(&mut self.writer, "a0", "fp", ret_buf_offset.unwrap());
                 }
 
                 writeln!(&mut self.writer, "  call {name}").unwrap();
+                if stack_args_size > 0 {
+                    addi(&mut self.writer, "sp", "sp", stack_args_size);
+                }
+
+                // 構造体の戻り値はレジスタから一時領域に書き戻し、そのアドレスを値とする
+                if let Some(offset) = ret_buf_offset {
+                    if ret_by_ref {
+                        addi(&mut self.writer, "a0", "fp", offset);
+                    } else {
+                        addi(&mut self.writer, "t1", "fp", offset);
+                        for part in ret_parts(&ctype) {
+                            store_part(
+                                &mut self.writer,
+                                part.reg(),
+                                "t1",
+                                part.offset as i32,
+                                part.size,
+                            );
+                        }
+                        writeln!(&mut self.writer, "  mv a0, t1").unwrap();
+                    }
+                }
+
+                if is_uint(&ctype) {
+                    zero_extend(&mut self.writer, 4);
+                }
             }
             TypedNodeKind::BinOp {
                 op: BinOp::Assign,
@@ -216,9 +506,12 @@ impl<'src> Codegen<'src> {
             } => {
                 self.count += 1;
                 let id = self.count;
+                let (lhs_ty, rhs_ty) = (lhs.ctype.clone().unwrap(), rhs.ctype.clone().unwrap());
                 self.gen_expr(*lhs);
+                cmp_zero(&mut self.writer, &lhs_ty.borrow());
                 writeln!(&mut self.writer, "  bne a0, zero, .L.or.true.{id}").unwrap();
                 self.gen_expr(*rhs);
+                cmp_zero(&mut self.writer, &rhs_ty.borrow());
                 writeln!(&mut self.writer, "  snez a0, a0").unwrap();
                 writeln!(&mut self.writer, "  j .L.or.end.{id}").unwrap();
                 writeln!(&mut self.writer, ".L.or.true.{id}:").unwrap();
@@ -232,15 +525,71 @@ impl<'src> Codegen<'src> {
             } => {
                 self.count += 1;
                 let id = self.count;
+                let (lhs_ty, rhs_ty) = (lhs.ctype.clone().unwrap(), rhs.ctype.clone().unwrap());
                 self.gen_expr(*lhs);
+                cmp_zero(&mut self.writer, &lhs_ty.borrow());
                 writeln!(&mut self.writer, "  beq a0, zero, .L.and.false.{id}").unwrap();
                 self.gen_expr(*rhs);
+                cmp_zero(&mut self.writer, &rhs_ty.borrow());
                 writeln!(&mut self.writer, "  snez a0, a0").unwrap();
                 writeln!(&mut self.writer, "  j .L.and.end.{id}").unwrap();
                 writeln!(&mut self.writer, ".L.and.false.{id}:").unwrap();
                 writeln!(&mut self.writer, "  li a0, 0").unwrap();
                 writeln!(&mut self.writer, ".L.and.end.{id}:").unwrap();
             }
+            TypedNodeKind::BinOp {
+                op: BinOp::Comma,
+                lhs,
+                rhs,
+            } => {
+                self.gen_expr(*lhs);
+                self.gen_expr(*rhs);
+            }
+            TypedNodeKind::BinOp { op, lhs, rhs }
+                if lhs.ctype.as_ref().unwrap().borrow().is_flonum() =>
+            {
+                self.gen_expr(*lhs.clone());
+                push_f(&mut self.writer, "fa0");
+                self.gen_expr(*rhs.clone());
+                push_f(&mut self.writer, "fa0");
+
+                pop_f(&mut self.writer, "ft1");
+                pop_f(&mut self.writer, "ft0");
+
+                let s = match lhs.ctype.unwrap().borrow().kind {
+                    CTypeKind::Float => "s",
+                    _ => "d",
+                };
+
+                match op {
+                    BinOp::Add => {
+                        writeln!(&mut self.writer, "  fadd.{s} fa0, ft0, ft1").unwrap();
+                    }
+                    BinOp::Sub => {
+                        writeln!(&mut self.writer, "  fsub.{s} fa0, ft0, ft1").unwrap();
+                    }
+                    BinOp::Mul => {
+                        writeln!(&mut self.writer, "  fmul.{s} fa0, ft0, ft1").unwrap();
+                    }
+                    BinOp::Div => {
+                        writeln!(&mut self.writer, "  fdiv.{s} fa0, ft0, ft1").unwrap();
+                    }
+                    BinOp::Eq => {
+                        writeln!(&mut self.writer, "  feq.{s} a0, ft0, ft1").unwrap();
+                    }
+                    BinOp::Ne => {
+                        writeln!(&mut self.writer, "  feq.{s} a0, ft0, ft1").unwrap();
+                        writeln!(&mut self.writer, "  xori a0, a0, 1").unwrap();
+                    }
+                    BinOp::Lt => {
+                        writeln!(&mut self.writer, "  flt.{s} a0, ft0, ft1").unwrap();
+                    }
+                    BinOp::Le => {
+                        writeln!(&mut self.writer, "  fle.{s} a0, ft0, ft1").unwrap();
+                    }
+                    _ => panic!("invalid operands to {op:?}"),
+                }
+            }
             TypedNodeKind::BinOp { op, lhs, rhs } => {
                 self.gen_expr(*lhs.clone());
                 push(&mut self.writer, "a0");
@@ -250,13 +599,13 @@ impl<'src> Codegen<'src> {
                 pop(&mut self.writer, "t1");
                 pop(&mut self.writer, "t0");
 
-                let postfix = match (
-                    lhs.ctype.map(|ty| ty.borrow().size).unwrap(),
-                    rhs.ctype.map(|ty| ty.borrow().size).unwrap(),
-                ) {
-                    (4, 4) => "w",
-                    (_, _) => "",
+                // 通常の算術変換後の lhs の型が 64 bit なら 64 bit 演算、そうでなければ 32 bit 演算
+                let lhs_ty = lhs.ctype.unwrap();
+                let postfix = match lhs_ty.borrow().kind {
+                    CTypeKind::Long | CTypeKind::Ptr(_) | CTypeKind::Array { .. } => "",
+                    _ => "w",
                 };
+                let u = if lhs_ty.borrow().is_unsigned { "u" } else { "" };
 
                 match op {
                     BinOp::Add => {
@@ -269,10 +618,29 @@ impl<'src> Codegen<'src> {
                         writeln!(&mut self.writer, "  mul{postfix} a0, t0, t1").unwrap();
                     }
                     BinOp::Div => {
-                        writeln!(&mut self.writer, "  div{postfix} a0, t0, t1").unwrap();
+                        writeln!(&mut self.writer, "  div{u}{postfix} a0, t0, t1").unwrap();
                     }
                     BinOp::Mod => {
-                        writeln!(&mut self.writer, "  rem{postfix} a0, t0, t1").unwrap();
+                        writeln!(&mut self.writer, "  rem{u}{postfix} a0, t0, t1").unwrap();
+                    }
+                    BinOp::BitAnd => {
+                        writeln!(&mut self.writer, "  and a0, t0, t1").unwrap();
+                    }
+                    BinOp::BitOr => {
+                        writeln!(&mut self.writer, "  or a0, t0, t1").unwrap();
+                    }
+                    BinOp::BitXor => {
+                        writeln!(&mut self.writer, "  xor a0, t0, t1").unwrap();
+                    }
+                    BinOp::Shl => {
+                        writeln!(&mut self.writer, "  sll{postfix} a0, t0, t1").unwrap();
+                    }
+                    // 右シフトは unsigned なら論理シフト、そうでなければ算術シフト
+                    BinOp::Shr if lhs_ty.borrow().is_unsigned => {
+                        writeln!(&mut self.writer, "  srl{postfix} a0, t0, t1").unwrap();
+                    }
+                    BinOp::Shr => {
+                        writeln!(&mut self.writer, "  sra{postfix} a0, t0, t1").unwrap();
                     }
                     BinOp::Eq => {
                         writeln!(&mut self.writer, "  xor a0, t0, t1").unwrap();
@@ -283,23 +651,65 @@ impl<'src> Codegen<'src> {
                         writeln!(&mut self.writer, "  snez a0, a0").unwrap();
                     }
                     BinOp::Lt => {
-                        writeln!(&mut self.writer, "  slt a0, t0, t1").unwrap();
+                        writeln!(&mut self.writer, "  slt{u} a0, t0, t1").unwrap();
                     }
                     BinOp::Le => {
-                        writeln!(&mut self.writer, "  slt a0, t1, t0").unwrap();
+                        writeln!(&mut self.writer, "  slt{u} a0, t1, t0").unwrap();
                         writeln!(&mut self.writer, "  xori a0, a0, 1").unwrap();
                     }
-                    BinOp::Comma => {
-                        writeln!(&mut self.writer, "  mv a0, t1").unwrap();
-                    }
                     _ => unreachable!(),
                 }
+
+                // 32 bit 演算の結果は符号拡張されるので、unsigned int ならゼロ拡張し直す
+                if matches!(
+                    op,
+                    BinOp::Add
+                        | BinOp::Sub
+                        | BinOp::Mul
+                        | BinOp::Div
+                        | BinOp::Mod
+                        | BinOp::Shl
+                        | BinOp::Shr
+                ) && is_uint(&ctype)
+                {
+                    zero_extend(&mut self.writer, 4);
+                }
             }
 
             _ => panic!("invalid expression"),
         }
     }
 
+    // a0 - min を添字にして、.rodata に置いた表から case ラベルへの相対位置を引いて飛ぶ
+    fn gen_jump_table(&mut self, count: usize, cases: &[i64], default_label: &str) {
+        let min = *cases.iter().min().unwrap();
+        let max = *cases.iter().max().unwrap();
+        let len = (max - min + 1) as usize;
+
+        writeln!(&mut self.writer, "  li t0, {min}").unwrap();
+        writeln!(&mut self.writer, "  sub t0, a0, t0").unwrap();
+        writeln!(&mut self.writer, "  li t1, {len}").unwrap();
+        writeln!(&mut self.writer, "  bgeu t0, t1, {default_label}").unwrap();
+        writeln!(&mut self.writer, "  slli t0, t0, 2").unwrap();
+        writeln!(&mut self.writer, "  la t1, .L.jt.{count}").unwrap();
+        writeln!(&mut self.writer, "  add t0, t1, t0").unwrap();
+        writeln!(&mut self.writer, "  lw t0, 0(t0)").unwrap();
+        writeln!(&mut self.writer, "  add t0, t1, t0").unwrap();
+        writeln!(&mut self.writer, "  jr t0").unwrap();
+
+        writeln!(&mut self.writer, "  .section .rodata").unwrap();
+        writeln!(&mut self.writer, "  .balign 4").unwrap();
+        writeln!(&mut self.writer, ".L.jt.{count}:").unwrap();
+        for value in min..=max {
+            let label = match cases.iter().position(|&case| case == value) {
+                Some(i) => format!(".L.case.{count}.{i}"),
+                None => default_label.to_string(),
+            };
+            writeln!(&mut self.writer, "  .word {label} - .L.jt.{count}").unwrap();
+        }
+        writeln!(&mut self.writer, "  .section .text").unwrap();
+    }
+
     fn gen_stmt(&mut self, node: TypedNode) {
         match node.kind {
             TypedNodeKind::For {
@@ -316,21 +726,112 @@ impl<'src> Codegen<'src> {
                 }
                 writeln!(&mut self.writer, ".L.begin.{count}:").unwrap();
                 if let Some(cond) = cond {
+                    let cond_ty = cond.ctype.clone().unwrap();
                     self.gen_expr(*cond);
-                    writeln!(&mut self.writer, "  beq a0, zero, .L.end.{count}").unwrap();
+                    cmp_zero(&mut self.writer, &cond_ty.borrow());
+                    writeln!(&mut self.writer, "  beq a0, zero, .L.break.{count}").unwrap();
                 }
+
+                self.brk_labels.push(count);
+                self.cont_labels.push(count);
                 self.gen_stmt(*then);
+                self.brk_labels.pop();
+                self.cont_labels.pop();
+
+                writeln!(&mut self.writer, ".L.continue.{count}:").unwrap();
                 if let Some(inc) = inc {
                     self.gen_expr(*inc);
                 }
                 writeln!(&mut self.writer, "  j .L.begin.{count}").unwrap();
-                writeln!(&mut self.writer, ".L.end.{count}:").unwrap();
+                writeln!(&mut self.writer, ".L.break.{count}:").unwrap();
+            }
+            TypedNodeKind::Switch {
+                cond,
+                then,
+                cases,
+                has_default,
+            } => {
+                self.count += 1;
+                let count = self.count;
+
+                self.gen_expr(*cond);
+
+                let default_label = if has_default {
+                    format!(".L.default.{count}")
+                } else {
+                    format!(".L.break.{count}")
+                };
+                if is_dense(&cases) {
+                    self.gen_jump_table(count, &cases, &default_label);
+                } else {
+                    for (i, value) in cases.iter().enumerate() {
+                        writeln!(&mut self.writer, "  li t0, {value}").unwrap();
+                        writeln!(&mut self.writer, "  beq a0, t0, .L.case.{count}.{i}").unwrap();
+                    }
+                    writeln!(&mut self.writer, "  j {default_label}").unwrap();
+                }
+
+                self.brk_labels.push(count);
+                self.switch_labels.push(count);
+                self.gen_stmt(*then);
+                self.brk_labels.pop();
+                self.switch_labels.pop();
+
+                writeln!(&mut self.writer, ".L.break.{count}:").unwrap();
+            }
+            TypedNodeKind::Case { index, body } => {
+                let count = self.switch_labels.last().unwrap();
+                writeln!(&mut self.writer, ".L.case.{count}.{index}:").unwrap();
+                self.gen_stmt(*body);
+            }
+            TypedNodeKind::Default(body) => {
+                let count = self.switch_labels.last().unwrap();
+                writeln!(&mut self.writer, ".L.default.{count}:").unwrap();
+                self.gen_stmt(*body);
+            }
+            TypedNodeKind::Goto(name) => {
+                let fn_name = self.current_fn_name.unwrap();
+                writeln!(&mut self.writer, "  j .L.label.{fn_name}.{name}").unwrap();
+            }
+            TypedNodeKind::Label { name, body } => {
+                let fn_name = self.current_fn_name.unwrap();
+                writeln!(&mut self.writer, ".L.label.{fn_name}.{name}:").unwrap();
+                self.gen_stmt(*body);
+            }
+            TypedNodeKind::Do { then, cond } => {
+                self.count += 1;
+                let count = self.count;
+
+                writeln!(&mut self.writer, ".L.begin.{count}:").unwrap();
+
+                self.brk_labels.push(count);
+                self.cont_labels.push(count);
+                self.gen_stmt(*then);
+                self.brk_labels.pop();
+                self.cont_labels.pop();
+
+                writeln!(&mut self.writer, ".L.continue.{count}:").unwrap();
+                let cond_ty = cond.ctype.clone().unwrap();
+                self.gen_expr(*cond);
+                cmp_zero(&mut self.writer, &cond_ty.borrow());
+                writeln!(&mut self.writer, "  bne a0, zero, .L.begin.{count}").unwrap();
+                writeln!(&mut self.writer, ".L.break.{count}:").unwrap();
+            }
+            TypedNodeKind::Break => {
+                let count = self.brk_labels.last().unwrap();
+                writeln!(&mut self.writer, "  j .L.break.{count}").unwrap();
+            }
+            TypedNodeKind::Continue => {
+                let count = self.cont_labels.last().unwrap();
+                writeln!(&mut self.writer, "  j .L.continue.{count}").unwrap();
             }
             TypedNodeKind::If { cond, then, els } => {
                 self.count += 1;
                 let count = self.count;
 
+                let cond_ty = cond.ctype.clone().unwrap();
                 self.gen_expr(*cond);
+                cmp_zero(&mut self.writer, &cond_ty.borrow());
                 writeln!(&mut self.writer, "  beq a0, zero, .L.else.{count}").unwrap();
 
                 self.gen_stmt(*then);
@@ -349,7 +850,23 @@ impl<'src> Codegen<'src> {
             }
             TypedNodeKind::Return(node) => {
                 if let Some(node) = node {
+                    let ret_ty = node.ctype.clone().unwrap();
                     self.gen_expr(*node);
+                    if is_uint(&ret_ty) {
+                        writeln!(&mut self.writer, "  sext.w a0, a0").unwrap();
+                    }
+
+                    if let Some(offset) = self.ret_buf_offset {
+                        // 呼び出し側から渡されたアドレスに構造体をコピーする
+                        writeln!(&mut self.writer, "  ld a1, {offset}(fp)").unwrap();
+                        copy_struct(&mut self.writer, &ret_ty.borrow());
+                        writeln!(&mut self.writer, "  mv a0, a1").unwrap();
+                    } else if ret_ty.borrow().is_struct_or_union() {
+                        writeln!(&mut self.writer, "  mv t1, a0").unwrap();
+                        for part in ret_parts(&ret_ty) {
+                            load_part(&mut self.writer, part.reg(), "t1", 0, &part);
+                        }
+                    }
                 }
                 writeln!(
                     &mut self.writer,
@@ -384,14 +901,38 @@ fn pop(writer: &mut Box<dyn Write>, reg: &str) {
     writeln!(writer, "  addi sp, sp, 8").unwrap();
 }
 
+fn push_f(writer: &mut Box<dyn Write>, reg: &str) {
+    writeln!(writer, "  # push {reg}").unwrap();
+    writeln!(writer, "  addi sp, sp, -8").unwrap();
+    writeln!(writer, "  fsd {reg}, 0(sp)").unwrap();
+}
+
+fn pop_f(writer: &mut Box<dyn Write>, reg: &str) {
+    writeln!(writer, "  # pop {reg}").unwrap();
+    writeln!(writer, "  fld {reg}, 0(sp)").unwrap();
+    writeln!(writer, "  addi sp, sp, 8").unwrap();
+}
+
 fn load(writer: &mut Box<dyn Write>, ty: &CTypeRef) {
-    if let CTypeKind::Array { .. } = ty.borrow().kind {
-        return;
+    // 配列や構造体はレジスタに載らないので、アドレスをそのまま値として扱う
+    match ty.borrow().kind {
+        CTypeKind::Array { .. } | CTypeKind::Struct { .. } | CTypeKind::Union { .. } => return,
+        CTypeKind::Float => {
+            writeln!(writer, "  flw fa0, 0(a0)").unwrap();
+            return;
+        }
+        CTypeKind::Double => {
+            writeln!(writer, "  fld fa0, 0(a0)").unwrap();
+            return;
+        }
+        _ => {}
     }
 
+    let u = if ty.borrow().is_unsigned { "u" } else { "" };
     match ty.borrow().size {
-        1 => writeln!(writer, "  lb a0, 0(a0)").unwrap(),
-        4 => writeln!(writer, "  lw a0, 0(a0)").unwrap(),
+        1 => writeln!(writer, "  lb{u} a0, 0(a0)").unwrap(),
+        2 => writeln!(writer, "  lh{u} a0, 0(a0)").unwrap(),
+        4 => writeln!(writer, "  lw{u} a0, 0(a0)").unwrap(),
         _ => writeln!(writer, "  ld a0, 0(a0)").unwrap(),
     }
 }
@@ -399,13 +940,407 @@ fn load(writer: &mut Box<dyn Write>, ty: &CTypeRef) {
 fn store(writer: &mut Box<dyn Write>, ty: &CTypeRef) {
     pop(writer, "a1");
 
+    match ty.borrow().kind {
+        CTypeKind::Struct { .. } | CTypeKind::Union { .. } => {
+            copy_struct(writer, &ty.borrow());
+            return;
+        }
+        CTypeKind::Float => {
+            writeln!(writer, "  fsw fa0, 0(a1)").unwrap();
+            return;
+        }
+        CTypeKind::Double => {
+            writeln!(writer, "  fsd fa0, 0(a1)").unwrap();
+            return;
+        }
+        _ => {}
+    }
+
     match ty.borrow().size {
         1 => writeln!(writer, "  sb a0, 0(a1)").unwrap(),
+        2 => writeln!(writer, "  sh a0, 0(a1)").unwrap(),
         4 => writeln!(writer, "  sw a0, 0(a1)").unwrap(),
         _ => writeln!(writer, "  sd a0, 0(a1)").unwrap(),
     }
 }
 
+// a0 が指す構造体を a1 が指す先にコピーする。アラインメントが許す限り大きな単位でコピーする
+fn copy_struct(writer: &mut Box<dyn Write>, ty: &CType) {
+    let (load, store, unit) = match ty.align {
+        8.. => ("ld", "sd", 8),
+        4 => ("lw", "sw", 4),
+        2 => ("lh", "sh", 2),
+        _ => ("lb", "sb", 1),
+    };
+
+    writeln!(writer, "  mv t1, a0").unwrap();
+    writeln!(writer, "  mv t2, a1").unwrap();
+
+    // 即値で表せるオフセットを超える場合はベースのアドレスを進める
+    let mut base = 0;
+    for offset in (0..ty.size).step_by(unit) {
+        if offset - base > 2040 {
+            writeln!(writer, "  li t0, {}", offset - base).unwrap();
+            writeln!(writer, "  add t1, t1, t0").unwrap();
+            writeln!(writer, "  add t2, t2, t0").unwrap();
+            base = offset;
+        }
+
+        writeln!(writer, "  {load} t0, {}(t1)", offset - base).unwrap();
+        writeln!(writer, "  {store} t0, {}(t2)", offset - base).unwrap();
+    }
+}
+
+// case の値が十分に密集していれば、比較の連鎖の代わりにジャンプテーブルを使う
+fn is_dense(cases: &[i64]) -> bool {
+    let (Some(&min), Some(&max)) = (cases.iter().min(), cases.iter().max()) else {
+        return false;
+    };
+
+    cases.len() >= 4
+        && max
+            .checked_sub(min)
+            .is_some_and(|range| range < 3 * cases.len() as i64)
+}
+
+// 8 バイト分の値を置く場所。引数レジスタを使い切った後は、呼び出し時の sp から 8 バイトずつ並ぶスロットに置く
+#[derive(Clone, Copy)]
+enum Slot {
+    Reg(&'static str),
+    Stack(usize),
+}
+
+// 引数や戻り値の構造体をレジスタで受け渡す際の、一つのレジスタ (またはスロット) に入る部分
+struct RegPart {
+    slot: Slot,
+    offset: usize,
+    size: usize,
+}
+
+impl RegPart {
+    // 戻り値は必ずレジスタに入る
+    fn reg(&self) -> &'static str {
+        match self.slot {
+            Slot::Reg(reg) => reg,
+            Slot::Stack(_) => unreachable!(),
+        }
+    }
+}
+
+// 引数の受け渡し方
+enum ArgLoc {
+    // スカラー値、または呼び出し側で作ったコピーへのアドレス (16 バイトを超える構造体) を渡す
+    Reg(&'static str),
+    // a7/fa7 まで使い切った後の引数はスタックのスロットに置く
+    Stack(usize),
+    // 16 バイト以下の構造体を最大 2 つのレジスタ (足りなければスロット) に分けて渡す
+    Struct(Vec<RegPart>),
+}
+
+impl ArgLoc {
+    fn slots(&self) -> Vec<Slot> {
+        match self {
+            ArgLoc::Reg(reg) => vec![Slot::Reg(reg)],
+            ArgLoc::Stack(n) => vec![Slot::Stack(*n)],
+            ArgLoc::Struct(parts) => parts.iter().map(|part| part.slot).collect(),
+        }
+    }
+}
+
+// 次の整数レジスタを割り当てる。a7 まで使い切っていればスタックのスロットにする
+fn next_gp(gp: &mut usize) -> Slot {
+    *gp += 1;
+    match ARG_REG.get(*gp - 1) {
+        Some(reg) => Slot::Reg(reg),
+        None => Slot::Stack(*gp - 1 - ARG_REG.len()),
+    }
+}
+
+fn is_fp_slot(slot: Slot) -> bool {
+    matches!(slot, Slot::Reg(reg) if is_fp_reg(reg))
+}
+
+fn is_fp_reg(reg: &str) -> bool {
+    reg.starts_with('f')
+}
+
+fn is_large_struct(ty: &CType) -> bool {
+    ty.is_struct_or_union() && ty.size > 16
+}
+
+// 構造体をスカラーのメンバに平坦化して (浮動小数点数か, offset, size) を集める。
+// union を含む場合やメンバが 2 つを超える場合は false を返す
+fn flatten_struct(ty: &CType, base: usize, fields: &mut Vec<(bool, usize, usize)>) -> bool {
+    match &ty.kind {
+        CTypeKind::Struct { members, .. } => members
+            .iter()
+            .all(|member| flatten_struct(&member.ty.borrow(), base + member.offset, fields)),
+        CTypeKind::Array {
+            base: elem, len, ..
+        } => {
+            let elem = elem.borrow();
+            (0..*len).all(|i| flatten_struct(&elem, base + i * elem.size, fields))
+        }
+        CTypeKind::Union { .. } => false,
+        _ => {
+            fields.push((ty.is_flonum(), base, ty.size));
+            fields.len() <= 2
+        }
+    }
+}
+
+// 16 バイト以下の構造体を渡すレジスタを決める。浮動小数点数のメンバが 1 つか 2 つで、
+// 残りが整数 1 つ以下なら fa レジスタを使い、そうでなければ 8 バイトずつ整数レジスタで渡す
+fn struct_parts(ty: &CType, is_variadic: bool, gp: &mut usize, fp: &mut usize) -> Vec<RegPart> {
+    let mut fields = vec![];
+    if !is_variadic && flatten_struct(ty, 0, &mut fields) && fields.iter().any(|&(is_fp, ..)| is_fp)
+    {
+        let nfp = fields.iter().filter(|&&(is_fp, ..)| is_fp).count();
+        if *fp + nfp <= FP_ARG_REG.len() && *gp + fields.len() - nfp <= ARG_REG.len() {
+            return fields
+                .into_iter()
+                .map(|(is_fp, offset, size)| {
+                    let slot = if is_fp {
+                        *fp += 1;
+                        Slot::Reg(FP_ARG_REG[*fp - 1])
+                    } else {
+                        next_gp(gp)
+                    };
+                    RegPart { slot, offset, size }
+                })
+                .collect();
+        }
+    }
+
+    (0..ty.size)
+        .step_by(8)
+        .map(|offset| RegPart {
+            slot: next_gp(gp),
+            offset,
+            size: (ty.size - offset).min(8),
+        })
+        .collect()
+}
+
+// psABI に従って各引数を渡すレジスタを決める。gp は最初に使う整数レジスタの番号。
+// fa0-fa7 から溢れた浮動小数点数は整数レジスタに、a0-a7 から溢れた引数はスタックに置く
+fn assign_arg_regs(tys: &[CTypeRef], variadic_from: Option<usize>, mut gp: usize) -> Vec<ArgLoc> {
+    let mut fp = 0;
+    tys.iter()
+        .enumerate()
+        .map(|(i, ty)| {
+            let ty = ty.borrow();
+            let is_variadic = variadic_from.is_some_and(|n| i >= n);
+            if ty.is_struct_or_union() && ty.size <= 16 {
+                return ArgLoc::Struct(struct_parts(&ty, is_variadic, &mut gp, &mut fp));
+            }
+            if ty.is_flonum() && !is_variadic && fp < FP_ARG_REG.len() {
+                fp += 1;
+                return ArgLoc::Reg(FP_ARG_REG[fp - 1]);
+            }
+            match next_gp(&mut gp) {
+                Slot::Reg(reg) => ArgLoc::Reg(reg),
+                Slot::Stack(n) => ArgLoc::Stack(n),
+            }
+        })
+        .collect()
+}
+
+// 16 バイト以下の構造体の戻り値は、最初の引数と同じ規則で a0/a1, fa0/fa1 に入れる
+fn ret_parts(ty: &CTypeRef) -> Vec<RegPart> {
+    match assign_arg_regs(std::slice::from_ref(ty), None, 0).pop() {
+        Some(ArgLoc::Struct(parts)) => parts,
+        _ => unreachable!(),
+    }
+}
+
+// base + offset から part を reg に読み込む。半端なサイズの場合は t0 を使って 1 バイトずつ組み立てる
+fn load_part(writer: &mut Box<dyn Write>, reg: &str, base: &str, offset: i32, part: &RegPart) {
+    let offset = offset + part.offset as i32;
+    if is_fp_reg(reg) {
+        let op = if part.size == 4 { "flw" } else { "fld" };
+        writeln!(writer, "  {op} {reg}, {offset}({base})").unwrap();
+        return;
+    }
+
+    match part.size {
+        1 => writeln!(writer, "  lbu {reg}, {offset}({base})").unwrap(),
+        2 => writeln!(writer, "  lhu {reg}, {offset}({base})").unwrap(),
+        4 => writeln!(writer, "  lwu {reg}, {offset}({base})").unwrap(),
+        8 => writeln!(writer, "  ld {reg}, {offset}({base})").unwrap(),
+        size => {
+            let last = offset + size as i32 - 1;
+            writeln!(writer, "  lbu {reg}, {last}({base})").unwrap();
+            for i in (offset..last).rev() {
+                writeln!(writer, "  slli {reg}, {reg}, 8").unwrap();
+                writeln!(writer, "  lbu t0, {i}({base})").unwrap();
+                writeln!(writer, "  or {reg}, {reg}, t0").unwrap();
+            }
+        }
+    }
+}
+
+// reg の下位 size バイトを base + offset に書き込む
+fn store_part(writer: &mut Box<dyn Write>, reg: &str, base: &str, offset: i32, size: usize) {
+    if is_fp_reg(reg) {
+        let op = if size == 4 { "fsw" } else { "fsd" };
+        writeln!(writer, "  {op} {reg}, {offset}({base})").unwrap();
+        return;
+    }
+
+    match size {
+        1 => writeln!(writer, "  sb {reg}, {offset}({base})").unwrap(),
+        2 => writeln!(writer, "  sh {reg}, {offset}({base})").unwrap(),
+        4 => writeln!(writer, "  sw {reg}, {offset}({base})").unwrap(),
+        8 => writeln!(writer, "  sd {reg}, {offset}({base})").unwrap(),
+        size => {
+            writeln!(writer, "  mv t0, {reg}").unwrap();
+            for i in offset..offset + size as i32 {
+                writeln!(writer, "  sb t0, {i}({base})").unwrap();
+                writeln!(writer, "  srli t0, t0, 8").unwrap();
+            }
+        }
+    }
+}
+
+fn is_uint(ty: &CTypeRef) -> bool {
+    let ty = ty.borrow();
+    ty.is_integer() && ty.is_unsigned && ty.size == 4
+}
+
+fn sign_extend(writer: &mut Box<dyn Write>, size: usize) {
+    match size {
+        4 => writeln!(writer, "  sext.w a0, a0").unwrap(),
+        _ => {
+            let shift = 64 - size * 8;
+            writeln!(writer, "  slli a0, a0, {shift}").unwrap();
+            writeln!(writer, "  srai a0, a0, {shift}").unwrap();
+        }
+    }
+}
+
+fn zero_extend(writer: &mut Box<dyn Write>, size: usize) {
+    match size {
+        1 => writeln!(writer, "  andi a0, a0, 255").unwrap(),
+        _ => {
+            let shift = 64 - size * 8;
+            writeln!(writer, "  slli a0, a0, {shift}").unwrap();
+            writeln!(writer, "  srli a0, a0, {shift}").unwrap();
+        }
+    }
+}
+
+// 浮動小数点数を 0 と比較し、0 でなければ 1 になる整数値を a0 に入れる
+fn cmp_zero(writer: &mut Box<dyn Write>, ty: &CType) {
+    match ty.kind {
+        CTypeKind::Float => {
+            writeln!(writer, "  fmv.w.x ft0, zero").unwrap();
+            writeln!(writer, "  feq.s a0, fa0, ft0").unwrap();
+            writeln!(writer, "  xori a0, a0, 1").unwrap();
+        }
+        CTypeKind::Double => {
+            writeln!(writer, "  fmv.d.x ft0, zero").unwrap();
+            writeln!(writer, "  feq.d a0, fa0, ft0").unwrap();
+            writeln!(writer, "  xori a0, a0, 1").unwrap();
+        }
+        _ => {}
+    }
+}
+
+// fcvt 命令で使う整数側のオペランドの名前
+fn fcvt_int_suffix(ty: &CType) -> &'static str {
+    match (ty.size, ty.is_unsigned) {
+        (8, false) => "l",
+        (8, true) => "lu",
+        (4, true) => "wu",
+        _ => "w",
+    }
+}
+
+fn cast(writer: &mut Box<dyn Write>, from: &CTypeRef, to: &CTypeRef) {
+    let from = from.borrow();
+    let to = to.borrow();
+
+    // _Bool への変換では 0 以外の値を全て 1 にする
+    if to.kind == CTypeKind::Bool {
+        if from.is_flonum() {
+            cmp_zero(writer, &from);
+        } else {
+            writeln!(writer, "  snez a0, a0").unwrap();
+        }
+        return;
+    }
+
+    if from.is_flonum() || to.is_flonum() {
+        let fsuffix = |ty: &CType| match ty.kind {
+            CTypeKind::Float => "s",
+            _ => "d",
+        };
+
+        match (from.is_flonum(), to.is_flonum()) {
+            (true, true) => {
+                if fsuffix(&from) != fsuffix(&to) {
+                    writeln!(
+                        writer,
+                        "  fcvt.{}.{} fa0, fa0",
+                        fsuffix(&to),
+                        fsuffix(&from)
+                    )
+                    .unwrap();
+                }
+            }
+            (false, true) => {
+                writeln!(
+                    writer,
+                    "  fcvt.{}.{} fa0, a0",
+                    fsuffix(&to),
+                    fcvt_int_suffix(&from)
+                )
+                .unwrap();
+            }
+            (true, false) => {
+                if !to.is_integer() {
+                    return;
+                }
+                // C では浮動小数点数から整数への変換は 0 方向への切り捨て
+                writeln!(
+                    writer,
+                    "  fcvt.{}.{} a0, fa0, rtz",
+                    fcvt_int_suffix(&to),
+                    fsuffix(&from)
+                )
+                .unwrap();
+                // fcvt.wu の結果も符号拡張されているので、unsigned int はゼロ拡張し直す
+                if to.size <= 4 && to.is_unsigned {
+                    zero_extend(writer, to.size);
+                } else if to.size < 4 {
+                    sign_extend(writer, to.size);
+                }
+            }
+            (false, false) => unreachable!(),
+        }
+        return;
+    }
+
+    // レジスタ上の整数は自身の型に従って符号拡張 (unsigned ならゼロ拡張) されているので、
+    // 64 bit の型への変換や、値がそのまま表現できる型への変換では何もしなくてよい
+    if !to.is_integer() || to.size == 8 {
+        return;
+    }
+    if from.is_integer()
+        && (from.size < to.size && (from.is_unsigned || !to.is_unsigned)
+            || from.size == to.size && from.is_unsigned == to.is_unsigned)
+    {
+        return;
+    }
+
+    if to.is_unsigned {
+        zero_extend(writer, to.size);
+    } else {
+        sign_extend(writer, to.size);
+    }
+}
+
 fn addi(writer: &mut Box<dyn Write>, reg1: &str, reg2: &str, imm: i32) {
     if (-2048..=2047).contains(&imm) {
         writeln!(writer, "  addi {reg1}, {reg2}, {imm}").unwrap();
@@ -2,17 +2,21 @@ use std::{cell::RefCell, rc::Rc};
 
 use crate::{
     lexer::Token,
-    parser::{BinOp, Member, Node, NodeKind, Object},
+    parser::{BinOp, InitData, Member, Node, NodeKind, Object},
 };
 
 pub type CTypeRef<'a> = Rc<RefCell<CType<'a>>>;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum TypedObject<'src> {
     Object {
+        id: usize,
         name: &'src str,
         ctype: CTypeRef<'src>,
         is_local: bool,
+        is_static: bool,
+        is_definition: bool,
+        init_data: Option<Vec<InitData>>,
     },
     StringLiteral {
         id: usize,
@@ -21,19 +25,33 @@ pub enum TypedObject<'src> {
     },
     Function {
         name: &'src str,
+        is_static: bool,
         node: Option<TypedNode<'src>>,
         locals: Vec<TypedObject<'src>>,
         params: Vec<TypedObject<'src>>,
+        ret_type: CTypeRef<'src>,
+        is_variadic: bool,
     },
 }
 
-impl<'src> TypedObject<'src> {
-    pub fn name(&self) -> Option<&'src str> {
-        if let TypedObject::Function { name, .. } | TypedObject::Object { name, .. } = self {
-            return Some(name);
+impl TypedObject<'_> {
+    // .data に置かれる変数のラベル。関数内の static 変数は同名のものがありうるので通し番号を付ける
+    pub fn label(&self) -> Option<String> {
+        match self {
+            TypedObject::Object {
+                name,
+                is_local: false,
+                ..
+            } => Some(name.to_string()),
+            TypedObject::Object {
+                id,
+                name,
+                is_static: true,
+                ..
+            } => Some(format!(".L..static.{name}.{id}")),
+            TypedObject::StringLiteral { id, .. } => Some(format!(".L..{id}")),
+            _ => None,
         }
-
-        None
     }
 }
 
@@ -41,25 +59,37 @@ impl<'src> From<Object<'src>> for TypedObject<'src> {
     fn from(kind: Object<'src>) -> Self {
         match kind {
             Object::Object {
+                id,
                 name,
                 ctype,
                 is_local,
+                is_static,
+                is_definition,
+                init_data,
             } => TypedObject::Object {
+                id,
                 name,
                 ctype,
                 is_local,
+                is_static,
+                is_definition,
+                init_data,
             },
             Object::StringLiteral { id, ctype, string } => {
                 TypedObject::StringLiteral { id, ctype, string }
             }
             Object::Function {
                 name,
+                is_static,
                 node,
                 locals,
                 params,
+                ret_type,
+                is_variadic,
                 ..
             } => TypedObject::Function {
                 name,
+                is_static,
                 node: (node).map(|n| n.into()),
                 locals: locals
                     .into_iter()
@@ -69,27 +99,50 @@ impl<'src> From<Object<'src>> for TypedObject<'src> {
                     .into_iter()
                     .map(|param| param.into())
                     .collect::<Vec<_>>(),
+                ret_type,
+                is_variadic,
             },
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct TypedNode<'src> {
     pub kind: TypedNodeKind<'src>,
     pub ctype: Option<CTypeRef<'src>>,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum TypedNodeKind<'src> {
-    Num(i32),
+    Num(i64),
+    FNum(f64),
     ExprStmt(Box<TypedNode<'src>>),
     Var(Box<TypedObject<'src>>),
     Return(Option<Box<TypedNode<'src>>>),
+    Break,
+    Continue,
+    Switch {
+        cond: Box<TypedNode<'src>>,
+        then: Box<TypedNode<'src>>,
+        cases: Vec<i64>,
+        has_default: bool,
+    },
+    Case {
+        index: usize,
+        body: Box<TypedNode<'src>>,
+    },
+    Default(Box<TypedNode<'src>>),
+    Goto(&'src str),
+    Label {
+        name: &'src str,
+        body: Box<TypedNode<'src>>,
+    },
     Block(Vec<TypedNode<'src>>),
     FuncCall {
         name: &'src str,
         args: Vec<TypedNode<'src>>,
+        variadic_from: Option<usize>,
+        ret_buf: Option<Box<TypedObject<'src>>>,
     },
     Addr(Box<TypedNode<'src>>),
     Deref(Box<TypedNode<'src>>),
@@ -104,6 +157,15 @@ pub enum TypedNodeKind<'src> {
         inc: Option<Box<TypedNode<'src>>>,
         then: Box<TypedNode<'src>>,
     },
+    Do {
+        then: Box<TypedNode<'src>>,
+        cond: Box<TypedNode<'src>>,
+    },
+    Cond {
+        cond: Box<TypedNode<'src>>,
+        then: Box<TypedNode<'src>>,
+        els: Box<TypedNode<'src>>,
+    },
     BinOp {
         op: BinOp,
         lhs: Box<TypedNode<'src>>,
@@ -113,34 +175,51 @@ pub enum TypedNodeKind<'src> {
         member: Member<'src>,
         node: Box<TypedNode<'src>>,
     },
+    Cast(Box<TypedNode<'src>>),
+    VaStart(Box<TypedNode<'src>>),
+    MemZero(Box<TypedNode<'src>>),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum CTypeKind<'src> {
     Void,
+    Bool,
     Int,
     Char,
+    Short,
+    Long,
+    Float,
+    Double,
+    Enum,
     Ptr(Box<CTypeRef<'src>> /* ポイント先の型 */),
     Function {
         return_ty: Box<CTypeRef<'src>>,
         params: Vec<CTypeRef<'src>>,
+        is_variadic: bool,
     },
     Array {
         base: Box<CTypeRef<'src>>,
         len: usize,
+        // int a[] のように長さが省略されたもの
+        is_incomplete: bool,
     },
     Struct {
         members: Vec<Member<'src>>,
         is_incomplete: bool,
     },
+    Union {
+        members: Vec<Member<'src>>,
+        is_incomplete: bool,
+    },
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct CType<'src> {
     pub kind: CTypeKind<'src>,
     pub name: Option<Token>,
     pub size: usize,
     pub align: usize,
+    pub is_unsigned: bool,
 }
 
 impl<'src> From<CType<'src>> for CTypeRef<'src> {
@@ -161,6 +240,18 @@ impl<'src> CType<'src> {
             name,
             size,
             align,
+            is_unsigned: false,
+        }
+        .into()
+    }
+
+    fn new_unsigned(kind: CTypeKind<'src>, size: usize, align: usize) -> CTypeRef<'src> {
+        CType {
+            kind,
+            name: None,
+            size,
+            align,
+            is_unsigned: true,
         }
         .into()
     }
@@ -171,6 +262,7 @@ impl<'src> CType<'src> {
             name: None,
             size: 8,
             align: 8,
+            is_unsigned: false,
         }
         .into()
     }
@@ -179,6 +271,10 @@ impl<'src> CType<'src> {
         CType::new(CTypeKind::Void, None, 0, 0)
     }
 
+    pub fn void() -> CTypeRef<'src> {
+        CType::new(CTypeKind::Void, None, 1, 1)
+    }
+
     pub fn int() -> CTypeRef<'src> {
         CType::new(CTypeKind::Int, None, 4, 4)
     }
@@ -186,6 +282,141 @@ impl<'src> CType<'src> {
     pub fn char() -> CTypeRef<'src> {
         CType::new(CTypeKind::Char, None, 1, 1)
     }
+
+    pub fn short() -> CTypeRef<'src> {
+        CType::new(CTypeKind::Short, None, 2, 2)
+    }
+
+    pub fn long() -> CTypeRef<'src> {
+        CType::new(CTypeKind::Long, None, 8, 8)
+    }
+
+    pub fn uchar() -> CTypeRef<'src> {
+        CType::new_unsigned(CTypeKind::Char, 1, 1)
+    }
+
+    pub fn ushort() -> CTypeRef<'src> {
+        CType::new_unsigned(CTypeKind::Short, 2, 2)
+    }
+
+    pub fn uint() -> CTypeRef<'src> {
+        CType::new_unsigned(CTypeKind::Int, 4, 4)
+    }
+
+    pub fn ulong() -> CTypeRef<'src> {
+        CType::new_unsigned(CTypeKind::Long, 8, 8)
+    }
+
+    pub fn float() -> CTypeRef<'src> {
+        CType::new(CTypeKind::Float, None, 4, 4)
+    }
+
+    pub fn double() -> CTypeRef<'src> {
+        CType::new(CTypeKind::Double, None, 8, 8)
+    }
+
+    pub fn bool() -> CTypeRef<'src> {
+        CType::new_unsigned(CTypeKind::Bool, 1, 1)
+    }
+
+    pub fn enum_type() -> CTypeRef<'src> {
+        CType::new(CTypeKind::Enum, None, 4, 4)
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self.kind,
+            CTypeKind::Bool
+                | CTypeKind::Char
+                | CTypeKind::Short
+                | CTypeKind::Int
+                | CTypeKind::Long
+                | CTypeKind::Enum
+        )
+    }
+
+    pub fn is_flonum(&self) -> bool {
+        matches!(self.kind, CTypeKind::Float | CTypeKind::Double)
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || self.is_flonum()
+    }
+
+    pub fn is_struct_or_union(&self) -> bool {
+        matches!(
+            self.kind,
+            CTypeKind::Struct { .. } | CTypeKind::Union { .. }
+        )
+    }
+
+    // 同じ変数や関数の宣言として両立する型か。長さが省略された配列は長さによらず両立する
+    pub fn is_compatible(&self, other: &CType<'src>) -> bool {
+        match (&self.kind, &other.kind) {
+            (CTypeKind::Ptr(base1), CTypeKind::Ptr(base2)) => {
+                base1.borrow().is_compatible(&base2.borrow())
+            }
+            (
+                CTypeKind::Array {
+                    base: base1,
+                    len: len1,
+                    is_incomplete: is_incomplete1,
+                },
+                CTypeKind::Array {
+                    base: base2,
+                    len: len2,
+                    is_incomplete: is_incomplete2,
+                },
+            ) => {
+                base1.borrow().is_compatible(&base2.borrow())
+                    && (*is_incomplete1 || *is_incomplete2 || len1 == len2)
+            }
+            (
+                CTypeKind::Function {
+                    return_ty: ret1,
+                    params: params1,
+                    is_variadic: variadic1,
+                },
+                CTypeKind::Function {
+                    return_ty: ret2,
+                    params: params2,
+                    is_variadic: variadic2,
+                },
+            ) => {
+                ret1.borrow().is_compatible(&ret2.borrow())
+                    && variadic1 == variadic2
+                    && params1.len() == params2.len()
+                    && params1
+                        .iter()
+                        .zip(params2)
+                        .all(|(p1, p2)| p1.borrow().is_compatible(&p2.borrow()))
+            }
+            (
+                CTypeKind::Struct {
+                    members: members1, ..
+                },
+                CTypeKind::Struct {
+                    members: members2, ..
+                },
+            )
+            | (
+                CTypeKind::Union {
+                    members: members1, ..
+                },
+                CTypeKind::Union {
+                    members: members2, ..
+                },
+            ) => {
+                members1.len() == members2.len()
+                    && members1.iter().zip(members2).all(|(m1, m2)| {
+                        m1.name == m2.name
+                            && m1.offset == m2.offset
+                            && m1.ty.borrow().is_compatible(&m2.ty.borrow())
+                    })
+            }
+            (kind1, kind2) => kind1 == kind2 && self.is_unsigned == other.is_unsigned,
+        }
+    }
 }
 
 pub fn array_of<'src>(base: CTypeRef<'src>, len: usize) -> CTypeRef<'src> {
@@ -195,6 +426,7 @@ pub fn array_of<'src>(base: CTypeRef<'src>, len: usize) -> CTypeRef<'src> {
         CTypeKind::Array {
             base: Box::new(base),
             len,
+            is_incomplete: false,
         },
         None,
         size * len,
@@ -202,23 +434,167 @@ pub fn array_of<'src>(base: CTypeRef<'src>, len: usize) -> CTypeRef<'src> {
     )
 }
 
+pub fn new_cast<'src>(node: TypedNode<'src>, ctype: CTypeRef<'src>) -> TypedNode<'src> {
+    TypedNode {
+        kind: TypedNodeKind::Cast(Box::new(node)),
+        ctype: Some(ctype),
+    }
+}
+
+// どちらかが浮動小数点数なら double, float の順に優先する。
+// 整数同士の演算では int より小さい型は int に昇格し、サイズの大きい方に揃える。
+// サイズが同じ場合はどちらかが unsigned なら unsigned になる
+fn get_common_type<'src>(ty1: &CType<'src>, ty2: &CType<'src>) -> CTypeRef<'src> {
+    if ty1.kind == CTypeKind::Double || ty2.kind == CTypeKind::Double {
+        return CType::double();
+    }
+    if ty1.kind == CTypeKind::Float || ty2.kind == CTypeKind::Float {
+        return CType::float();
+    }
+
+    let promote = |ty: &CType<'src>| {
+        if ty.size < 4 {
+            (4, false)
+        } else {
+            (ty.size, ty.is_unsigned)
+        }
+    };
+
+    let (size1, is_unsigned1) = promote(ty1);
+    let (size2, is_unsigned2) = promote(ty2);
+
+    let (size, is_unsigned) = if size1 == size2 {
+        (size1, is_unsigned1 || is_unsigned2)
+    } else if size1 < size2 {
+        (size2, is_unsigned2)
+    } else {
+        (size1, is_unsigned1)
+    };
+
+    match (size, is_unsigned) {
+        (8, false) => CType::long(),
+        (8, true) => CType::ulong(),
+        (_, false) => CType::int(),
+        (_, true) => CType::uint(),
+    }
+}
+
+fn usual_arith_conv<'src>(
+    lhs: TypedNode<'src>,
+    rhs: TypedNode<'src>,
+) -> (TypedNode<'src>, TypedNode<'src>) {
+    let lhs_ty = lhs.ctype.clone().unwrap();
+    let rhs_ty = rhs.ctype.clone().unwrap();
+    if !lhs_ty.borrow().is_numeric() || !rhs_ty.borrow().is_numeric() {
+        return (lhs, rhs);
+    }
+
+    let ty = get_common_type(&lhs_ty.borrow(), &rhs_ty.borrow());
+    (new_cast(lhs, Rc::clone(&ty)), new_cast(rhs, ty))
+}
+
+// int より小さい整数型を int に格上げする
+fn integer_promotion(node: TypedNode) -> TypedNode {
+    let ty = node.ctype.clone().unwrap();
+    if ty.borrow().is_integer() && ty.borrow().size < 4 {
+        return new_cast(node, CType::int());
+    }
+
+    node
+}
+
+// 値が 0 の整数定数か、それを void * にキャストしたもの
+fn is_null_pointer_constant(node: &TypedNode) -> bool {
+    match &node.kind {
+        TypedNodeKind::Num(0) => true,
+        TypedNodeKind::Cast(inner) => match &node.ctype.as_ref().unwrap().borrow().kind {
+            CTypeKind::Ptr(base) => {
+                base.borrow().kind == CTypeKind::Void && matches!(inner.kind, TypedNodeKind::Num(0))
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+// 条件演算子の 2 つの値を共通の型に揃える。数値同士は通常の算術変換を行い、
+// ポインタと空ポインタ定数ではポインタ型に、void * と他のポインタでは void * に揃える。
+// 配列は先頭要素へのポインタとして扱い、構造体や void はそのままの型になる
+fn cond_arms<'src>(
+    then: TypedNode<'src>,
+    els: TypedNode<'src>,
+) -> (TypedNode<'src>, TypedNode<'src>) {
+    let decay = |node: TypedNode<'src>| {
+        let ty = node.ctype.clone().unwrap();
+        let base = match &ty.borrow().kind {
+            CTypeKind::Array { base, .. } => Rc::clone(base),
+            _ => return node,
+        };
+        new_cast(node, CType::pointer_to(base))
+    };
+    let then = decay(then);
+    let els = decay(els);
+
+    let then_ty = then.ctype.clone().unwrap();
+    let els_ty = els.ctype.clone().unwrap();
+    if then_ty.borrow().is_numeric() && els_ty.borrow().is_numeric() {
+        return usual_arith_conv(then, els);
+    }
+
+    let is_ptr = |ty: &CTypeRef| matches!(ty.borrow().kind, CTypeKind::Ptr(_));
+    let is_void_ptr = |ty: &CTypeRef| match &ty.borrow().kind {
+        CTypeKind::Ptr(base) => base.borrow().kind == CTypeKind::Void,
+        _ => false,
+    };
+    if is_ptr(&then_ty) && is_null_pointer_constant(&els) {
+        return (then, new_cast(els, then_ty));
+    }
+    if is_ptr(&els_ty) && is_null_pointer_constant(&then) {
+        return (new_cast(then, els_ty), els);
+    }
+    if is_void_ptr(&els_ty) && is_ptr(&then_ty) {
+        return (new_cast(then, els_ty), els);
+    }
+    if is_void_ptr(&then_ty) && is_ptr(&els_ty) {
+        return (then, new_cast(els, then_ty));
+    }
+
+    (then, els)
+}
+
 impl<'src> From<Node<'src>> for TypedNode<'src> {
     fn from(node: Node<'src>) -> TypedNode<'src> {
         match node.kind {
             NodeKind::Num(value) => TypedNode {
                 kind: TypedNodeKind::Num(value),
-                ctype: Some(CType::int()),
+                ctype: Some(if i32::try_from(value).is_ok() {
+                    CType::int()
+                } else {
+                    CType::long()
+                }),
+            },
+            NodeKind::FNum(value) => TypedNode {
+                kind: TypedNodeKind::FNum(value),
+                ctype: Some(CType::double()),
             },
             NodeKind::Var(object) => match *object {
                 Object::Object {
+                    id,
                     name,
                     ctype,
                     is_local,
+                    is_static,
+                    is_definition,
+                    ..
                 } => TypedNode {
                     kind: TypedNodeKind::Var(Box::new(TypedObject::Object {
+                        id,
                         name,
                         ctype: Rc::clone(&ctype),
                         is_local,
+                        is_static,
+                        is_definition,
+                        init_data: None,
                     })),
                     ctype: Some(ctype),
                 },
@@ -239,8 +615,7 @@ impl<'src> From<Node<'src>> for TypedNode<'src> {
                 lhs,
                 rhs,
             } => {
-                let lhs = (*lhs).into();
-                let rhs = (*rhs).into();
+                let (lhs, rhs) = usual_arith_conv((*lhs).into(), (*rhs).into());
 
                 TypedNode {
                     kind: TypedNodeKind::BinOp {
@@ -264,6 +639,14 @@ impl<'src> From<Node<'src>> for TypedNode<'src> {
                 }
 
                 let lhs_ctype = lhs.ctype.clone();
+                let rhs = if matches!(
+                    lhs_ctype.as_ref().unwrap().borrow().kind,
+                    CTypeKind::Struct { .. } | CTypeKind::Union { .. }
+                ) {
+                    rhs
+                } else {
+                    new_cast(rhs, Rc::clone(lhs_ctype.as_ref().unwrap()))
+                };
 
                 TypedNode {
                     kind: TypedNodeKind::BinOp {
@@ -274,6 +657,23 @@ impl<'src> From<Node<'src>> for TypedNode<'src> {
                     ctype: lhs_ctype,
                 }
             }
+            // シフトの結果は格上げした左辺の型になり、右辺は左辺と揃えない
+            NodeKind::BinOp {
+                op: op @ (BinOp::Shl | BinOp::Shr),
+                lhs,
+                rhs,
+            } => {
+                let lhs = integer_promotion((*lhs).into());
+                let rhs = integer_promotion((*rhs).into());
+                TypedNode {
+                    ctype: lhs.ctype.clone(),
+                    kind: TypedNodeKind::BinOp {
+                        op,
+                        lhs: Box::new(lhs),
+                        rhs: Box::new(rhs),
+                    },
+                }
+            }
             NodeKind::BinOp {
                 op: op @ (BinOp::LogAnd | BinOp::LogOr),
                 lhs,
@@ -300,49 +700,48 @@ impl<'src> From<Node<'src>> for TypedNode<'src> {
                     lhs.ctype.as_ref().map(|ty| ty.borrow().clone()),
                     rhs.ctype.as_ref().map(|ty| ty.borrow().clone()),
                 ) {
-                    // (int | char) _ (int | char) -> int
-                    (
-                        _,
-                        Some(CType {
-                            kind: CTypeKind::Int | CTypeKind::Char,
-                            ..
-                        }),
-                        Some(CType {
-                            kind: CTypeKind::Int | CTypeKind::Char,
-                            ..
-                        }),
-                    ) => TypedNode {
+                    (BinOp::Comma, _, rhs_ty) => TypedNode {
                         kind: TypedNodeKind::BinOp {
                             op,
                             lhs: Box::new(lhs),
-                            rhs: Box::new(rhs),
+                            rhs: Box::new(rhs.clone()),
                         },
-                        ctype: Some(CType::int()),
+                        ctype: rhs_ty.map(|ty| ty.clone().into()),
                     },
-                    // (int | char) + ptr -> ptr
+                    // number _ number -> 共通の型
+                    (_, Some(lhs_ty), Some(rhs_ty))
+                        if lhs_ty.is_numeric() && rhs_ty.is_numeric() =>
+                    {
+                        let ctype = get_common_type(&lhs_ty, &rhs_ty);
+
+                        TypedNode {
+                            kind: TypedNodeKind::BinOp {
+                                op,
+                                lhs: Box::new(new_cast(lhs, Rc::clone(&ctype))),
+                                rhs: Box::new(new_cast(rhs, Rc::clone(&ctype))),
+                            },
+                            ctype: Some(ctype),
+                        }
+                    }
+                    // integer + ptr -> ptr
                     (
                         BinOp::Add,
-                        Some(CType {
-                            kind: CTypeKind::Int | CTypeKind::Char,
-                            ..
-                        }),
+                        Some(lhs_ty),
                         Some(CType {
                             kind: CTypeKind::Ptr(ctype) | CTypeKind::Array { base: ctype, .. },
                             ..
                         }),
-                    ) => {
+                    ) if lhs_ty.is_integer() => {
                         let lhs = TypedNode {
                             kind: TypedNodeKind::BinOp {
                                 op: BinOp::Mul,
-                                lhs: Box::new(lhs),
-                                rhs: Box::new(
-                                    (Node {
-                                        kind: NodeKind::Num(ctype.borrow().size as i32),
-                                    })
-                                    .into(),
-                                ),
+                                lhs: Box::new(new_cast(lhs, CType::long())),
+                                rhs: Box::new(TypedNode {
+                                    kind: TypedNodeKind::Num(ctype.borrow().size as i64),
+                                    ctype: Some(CType::long()),
+                                }),
                             },
-                            ctype: Some(CType::int()),
+                            ctype: Some(CType::long()),
                         };
 
                         TypedNode {
@@ -354,30 +753,25 @@ impl<'src> From<Node<'src>> for TypedNode<'src> {
                             ctype: Some(CType::pointer_to(Rc::clone(&*ctype))),
                         }
                     }
-                    // ptr + (int | char), ptr - (int | char)
+                    // ptr + integer, ptr - integer
                     (
                         BinOp::Add | BinOp::Sub,
                         Some(CType {
                             kind: CTypeKind::Ptr(ctype) | CTypeKind::Array { base: ctype, .. },
                             ..
                         }),
-                        Some(CType {
-                            kind: CTypeKind::Int | CTypeKind::Char,
-                            ..
-                        }),
-                    ) => {
+                        Some(rhs_ty),
+                    ) if rhs_ty.is_integer() => {
                         let rhs = TypedNode {
                             kind: TypedNodeKind::BinOp {
                                 op: BinOp::Mul,
-                                lhs: Box::new(rhs),
-                                rhs: Box::new(
-                                    (Node {
-                                        kind: NodeKind::Num(ctype.borrow().size as i32),
-                                    })
-                                    .into(),
-                                ),
+                                lhs: Box::new(new_cast(rhs, CType::long())),
+                                rhs: Box::new(TypedNode {
+                                    kind: TypedNodeKind::Num(ctype.borrow().size as i64),
+                                    ctype: Some(CType::long()),
+                                }),
                             },
-                            ctype: Some(CType::int()),
+                            ctype: Some(CType::long()),
                         };
 
                         TypedNode {
@@ -389,7 +783,7 @@ impl<'src> From<Node<'src>> for TypedNode<'src> {
                             ctype: Some(CType::pointer_to(Rc::clone(&ctype))),
                         }
                     }
-                    // ptr - ptr
+                    // ptr - ptr -> long
                     (
                         BinOp::Sub,
                         Some(CType {
@@ -411,31 +805,21 @@ impl<'src> From<Node<'src>> for TypedNode<'src> {
                                 lhs: Box::new(lhs.clone()),
                                 rhs: Box::new(rhs),
                             },
-                            ctype: Some(CType::int()),
+                            ctype: Some(CType::long()),
                         };
 
                         TypedNode {
                             kind: TypedNodeKind::BinOp {
                                 op: BinOp::Div,
                                 lhs: Box::new(typed_node),
-                                rhs: Box::new(
-                                    (Node {
-                                        kind: NodeKind::Num(lhs_basety.borrow().size as i32),
-                                    })
-                                    .into(),
-                                ),
+                                rhs: Box::new(TypedNode {
+                                    kind: TypedNodeKind::Num(lhs_basety.borrow().size as i64),
+                                    ctype: Some(CType::long()),
+                                }),
                             },
-                            ctype: Some(CType::int()),
+                            ctype: Some(CType::long()),
                         }
                     }
-                    (BinOp::Comma, _, rhs_ty) => TypedNode {
-                        kind: TypedNodeKind::BinOp {
-                            op,
-                            lhs: Box::new(lhs),
-                            rhs: Box::new(rhs.clone()),
-                        },
-                        ctype: rhs_ty.map(|ty| ty.clone().into()),
-                    },
 
                     // else
                     // TODO: これ本当は wildcard にしない方がいい気がする
@@ -444,10 +828,18 @@ impl<'src> From<Node<'src>> for TypedNode<'src> {
                     }
                 }
             }
-            NodeKind::FuncCall { name, args, ret_ty } => TypedNode {
+            NodeKind::FuncCall {
+                name,
+                args,
+                ret_ty,
+                variadic_from,
+                ret_buf,
+            } => TypedNode {
                 kind: TypedNodeKind::FuncCall {
                     name,
                     args: args.into_iter().map(|arg| arg.into()).collect::<Vec<_>>(),
+                    variadic_from,
+                    ret_buf: ret_buf.map(|obj| Box::new((*obj).into())),
                 },
                 ctype: Some(ret_ty),
             },
@@ -464,9 +856,12 @@ impl<'src> From<Node<'src>> for TypedNode<'src> {
                         }),
                         _,
                     ) => CType::pointer_to(Rc::clone(&base)),
-                    (Some(ty), TypedNodeKind::Var { .. } | TypedNodeKind::Deref(_)) => {
-                        CType::pointer_to(ty.clone().into())
-                    }
+                    (
+                        Some(ty),
+                        TypedNodeKind::Var { .. }
+                        | TypedNodeKind::Deref(_)
+                        | TypedNodeKind::Member { .. },
+                    ) => CType::pointer_to(ty.clone().into()),
                     _ => panic!("invalid operand for &"),
                 };
 
@@ -513,6 +908,68 @@ impl<'src> From<Node<'src>> for TypedNode<'src> {
                     ctype: None,
                 }
             }
+            NodeKind::Break => TypedNode {
+                kind: TypedNodeKind::Break,
+                ctype: None,
+            },
+            NodeKind::Continue => TypedNode {
+                kind: TypedNodeKind::Continue,
+                ctype: None,
+            },
+            NodeKind::Switch {
+                cond,
+                then,
+                cases,
+                has_default,
+            } => TypedNode {
+                kind: TypedNodeKind::Switch {
+                    cond: Box::new((*cond).into()),
+                    then: Box::new((*then).into()),
+                    cases,
+                    has_default,
+                },
+                ctype: None,
+            },
+            NodeKind::Case { index, body } => TypedNode {
+                kind: TypedNodeKind::Case {
+                    index,
+                    body: Box::new((*body).into()),
+                },
+                ctype: None,
+            },
+            NodeKind::Default(body) => TypedNode {
+                kind: TypedNodeKind::Default(Box::new((*body).into())),
+                ctype: None,
+            },
+            NodeKind::Do { then, cond } => TypedNode {
+                kind: TypedNodeKind::Do {
+                    then: Box::new((*then).into()),
+                    cond: Box::new((*cond).into()),
+                },
+                ctype: None,
+            },
+            NodeKind::Cond { cond, then, els } => {
+                let (then, els) = cond_arms((*then).into(), (*els).into());
+                TypedNode {
+                    ctype: then.ctype.clone(),
+                    kind: TypedNodeKind::Cond {
+                        cond: Box::new((*cond).into()),
+                        then: Box::new(then),
+                        els: Box::new(els),
+                    },
+                }
+            }
+            NodeKind::Goto(name) => TypedNode {
+                kind: TypedNodeKind::Goto(name),
+                ctype: None,
+            },
+            NodeKind::Label { name, body } => TypedNode {
+                kind: TypedNodeKind::Label {
+                    name,
+                    body: Box::new((*body).into()),
+                },
+                ctype: None,
+            },
             NodeKind::Block(nodes) => {
                 let typed_nodes = nodes
                     .into_iter()
@@ -563,6 +1020,15 @@ impl<'src> From<Node<'src>> for TypedNode<'src> {
                     ctype,
                 }
             }
+            NodeKind::Cast { node, ctype } => new_cast((*node).into(), ctype),
+            NodeKind::VaStart(ap) => TypedNode {
+                kind: TypedNodeKind::VaStart(Box::new((*ap).into())),
+                ctype: Some(CType::void()),
+            },
+            NodeKind::MemZero(var) => TypedNode {
+                kind: TypedNodeKind::MemZero(Box::new((*var).into())),
+                ctype: Some(CType::void()),
+            },
         }
     }
 }
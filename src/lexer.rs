@@ -1,12 +1,26 @@
 use crate::{SourceMap, escape::unescape};
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum TokenKind {
     Reserved,
     Ident,
-    Num(i32),
+    Num {
+        value: i64,
+        is_unsigned: bool,
+        is_long: bool,
+    },
+    Float {
+        value: f64,
+        is_double: bool,
+    },
     String(String),
     Char(char),
+    // プリプロセス中の数値。"1.2.3" や "32.h" のように数値にならないものもありうるので、
+    // プリプロセスが終わってから Num か Float にする
+    PPNum,
+    // 閉じていない引用符など、トークンにならない文字。#if 0 で読み飛ばすグループの中には
+    // 書けるので、プリプロセスが終わっても残っていたときに中のメッセージでエラーにする
+    Invalid(&'static str),
     Eof,
 }
 
@@ -16,16 +30,68 @@ pub struct Span {
     pub hi: usize,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Token {
     pub kind: TokenKind,
     pub span: Span,
+    // 行頭のトークンか。プリプロセッサのディレクティブの判定に使う
+    pub at_bol: bool,
+    // 直前に空白があるか
+    pub has_space: bool,
+    // このトークンを生んだ展開で使ったマクロの名前。これらのマクロはもう展開しない
+    pub hideset: Vec<String>,
+    // マクロの展開結果なら、いちばん外側のマクロを書いた位置。__LINE__ やエラーの表示で使う
+    pub origin: Option<Span>,
+}
+
+impl Token {
+    // エラーで示す位置。マクロの展開結果なら、置換リストや ## で作った字句ではなくマクロを書いた位置にする
+    pub fn error_span(&self) -> &Span {
+        self.origin.as_ref().unwrap_or(&self.span)
+    }
+}
+
+const KEYWORDS: [&str; 30] = [
+    "return", "if", "else", "for", "while", "int", "sizeof", "char", "void", "struct", "const",
+    "short", "long", "signed", "unsigned", "float", "double", "typedef", "enum", "union", "_Bool",
+    "break", "continue", "switch", "case", "default", "goto", "do", "static", "extern",
+];
+
+// キーワードはマクロの名前にもなりうるので、プリプロセスが終わってから識別子と区別する。
+// 数値もここで値にする
+pub fn convert_pp_tokens<'src>(source_map: &'src SourceMap<'src>, tokens: &mut [Token]) {
+    for token in tokens {
+        if let TokenKind::Invalid(message) = token.kind {
+            source_map.error_at(token.error_span(), message);
+        }
+        if token.kind == TokenKind::Ident && KEYWORDS.contains(&source_map.span_to_str(&token.span))
+        {
+            token.kind = TokenKind::Reserved;
+        }
+        if token.kind == TokenKind::PPNum {
+            token.kind = convert_pp_number(source_map, token);
+        }
+    }
+}
+
+pub fn convert_pp_number<'src>(source_map: &'src SourceMap<'src>, token: &Token) -> TokenKind {
+    let text = source_map.span_to_str(&token.span);
+    let mut lexer = Lexer::new(source_map, text, token.span.lo);
+    let kind = lexer.read_number();
+    if lexer.cursor != text.len() {
+        lexer.error_at(0, text.len(), "invalid number literal");
+    }
+    kind
 }
 
 pub struct Lexer<'src> {
     source: &'src str,
     cursor: usize,
     source_map: &'src SourceMap<'src>,
+    // source の先頭の、SourceMap 上での位置
+    base: usize,
+    at_bol: bool,
+    has_space: bool,
 }
 
 fn is_ident_first(c: char) -> bool {
@@ -37,14 +103,44 @@ fn is_ident_follow(c: char) -> bool {
 }
 
 impl<'src> Lexer<'src> {
-    pub fn new(source_map: &'src SourceMap, source: &'src str) -> Self {
+    pub fn new(source_map: &'src SourceMap<'src>, source: &'src str, base: usize) -> Self {
         Self {
             source,
             cursor: 0,
             source_map,
+            base,
+            at_bol: true,
+            has_space: false,
         }
     }
 
+    fn new_token(&mut self, kind: TokenKind, lo: usize, hi: usize) -> Token {
+        let token = Token {
+            kind,
+            span: Span {
+                lo: self.base + lo,
+                hi: self.base + hi,
+            },
+            at_bol: self.at_bol,
+            has_space: self.has_space,
+            hideset: vec![],
+            origin: None,
+        };
+        self.at_bol = false;
+        self.has_space = false;
+        token
+    }
+
+    fn error_at(&self, lo: usize, hi: usize, message: &str) -> ! {
+        self.source_map.error_at(
+            &Span {
+                lo: self.base + lo,
+                hi: self.base + hi,
+            },
+            message,
+        )
+    }
+
     pub fn lex(&mut self) -> Vec<Token> {
         let mut tokens = vec![];
 
@@ -53,9 +149,12 @@ impl<'src> Lexer<'src> {
 
             if self.source[self.cursor..].starts_with("//") {
                 self.cursor += 2;
-                while !self.source[self.cursor..].starts_with('\n') {
+                while self.cursor < self.source.len()
+                    && !self.source[self.cursor..].starts_with('\n')
+                {
                     self.cursor += 1;
                 }
+                self.has_space = true;
 
                 continue;
             }
@@ -65,86 +164,61 @@ impl<'src> Lexer<'src> {
                 if let Some(offset) = self.source[self.cursor..].find("*/") {
                     self.cursor += offset + 2;
                 } else {
-                    self.source_map.error_at(
-                        &Span {
-                            lo: self.cursor,
-                            hi: self.cursor,
-                        },
-                        "unclosed block comment",
-                    );
+                    self.error_at(self.cursor, self.cursor, "unclosed block comment");
                 }
+                self.has_space = true;
+
+                continue;
+            }
 
+            // 行末の \ は次の行とつなげる
+            if self.source[self.cursor..].starts_with("\\\n") {
+                self.cursor += 2;
+                self.has_space = true;
+                continue;
+            }
+
+            if c == '\n' {
+                self.cursor += 1;
+                self.at_bol = true;
+                self.has_space = false;
                 continue;
             }
 
             if c.is_whitespace() {
                 self.cursor += 1;
+                self.has_space = true;
                 continue;
             }
 
-            for keyword in [
-                "return", "if", "else", "for", "while", "int", "sizeof", "char", "void", "struct",
-                "const",
-            ] {
-                if let Some(rest) = self.source[self.cursor..].strip_prefix(keyword) {
-                    if rest.is_empty() || !is_ident_follow(rest.chars().next().unwrap()) {
-                        tokens.push(Token {
-                            kind: TokenKind::Reserved,
-                            span: Span {
-                                lo: self.cursor,
-                                hi: self.cursor + keyword.len(),
-                            },
-                        });
-                        self.cursor += keyword.len();
-                        continue 'outer;
-                    }
-                }
+            // `.5` のような小数点から始まる浮動小数点数リテラルも数値として読む
+            if c.is_ascii_digit()
+                || (c == '.'
+                    && self.source[self.cursor + 1..].starts_with(|c: char| c.is_ascii_digit()))
+            {
+                let start = self.cursor;
+                self.skip_pp_number();
+
+                let token = self.new_token(TokenKind::PPNum, start, self.cursor);
+                tokens.push(token);
+                continue;
             }
 
             for punct in [
-                "==", "!=", "<=", ">=", "||", "&&", "+=", "-=", "*=", "/=", "++", "--", "->", "+",
-                "-", "*", "/", "{", "}", "(", ")", "<", ">", ";", "=", "&", ",", "[", "]", "%",
-                "!", ".",
+                "<<=", ">>=", "...", "==", "!=", "<=", ">=", "||", "&&", "+=", "-=", "*=", "/=",
+                "%=", "&=", "|=", "^=", "<<", ">>", "++", "--", "->", "##", "+", "-", "*", "/",
+                "{", "}", "(", ")", "<", ">", ";", "=", "&", ",", "[", "]", "%", "!", ".", ":",
+                "?", "|", "^", "~", "#",
             ] {
                 if self.source[self.cursor..].starts_with(punct) {
-                    tokens.push(Token {
-                        kind: TokenKind::Reserved,
-                        span: Span {
-                            lo: self.cursor,
-                            hi: self.cursor + punct.len(),
-                        },
-                    });
+                    let token =
+                        self.new_token(TokenKind::Reserved, self.cursor, self.cursor + punct.len());
+                    tokens.push(token);
                     self.cursor += punct.len();
                     continue 'outer;
                 }
             }
 
-            if c.is_ascii_digit() {
-                let start = self.cursor;
-                while self.cursor < self.source.len()
-                    && self.source[self.cursor..]
-                        .chars()
-                        .next()
-                        .unwrap()
-                        .is_ascii_digit()
-                {
-                    self.cursor += 1;
-                }
-
-                tokens.push(Token {
-                    kind: TokenKind::Num(
-                        self.source[start..self.cursor]
-                            .parse::<i32>()
-                            .expect("数字へのパースに失敗"),
-                    ),
-                    span: Span {
-                        lo: start,
-                        hi: self.cursor,
-                    },
-                });
-                continue;
-            }
-
             if c == '"' {
                 let start = self.cursor;
                 self.cursor += 1;
@@ -157,32 +231,29 @@ impl<'src> Lexer<'src> {
                         continue;
                     }
 
-                    if c == '"' {
+                    if c == '"' || c == '\n' {
                         break;
                     }
 
                     self.cursor += 1;
                 }
+                if !self.source[self.cursor..].starts_with('"') {
+                    let token = self.new_token(
+                        TokenKind::Invalid("unclosed string literal"),
+                        start,
+                        self.cursor,
+                    );
+                    tokens.push(token);
+                    continue;
+                }
 
-                tokens.push(Token {
-                    kind: TokenKind::String(
-                        unescape(&self.source[(start + 1)..self.cursor]).unwrap_or_else(
-                            |(pos1, pos2)| {
-                                self.source_map.error_at(
-                                    &Span {
-                                        lo: start + pos1,
-                                        hi: start + pos2,
-                                    },
-                                    "failed to unescape",
-                                )
-                            },
-                        ),
-                    ),
-                    span: Span {
-                        lo: start,
-                        hi: (self.cursor + 1),
+                let string = unescape(&self.source[(start + 1)..self.cursor]).unwrap_or_else(
+                    |(pos1, pos2)| {
+                        self.error_at(start + 1 + pos1, start + 1 + pos2, "failed to unescape")
                     },
-                });
+                );
+                let token = self.new_token(TokenKind::String(string), start, self.cursor + 1);
+                tokens.push(token);
 
                 self.cursor += 1;
                 continue;
@@ -200,34 +271,31 @@ impl<'src> Lexer<'src> {
                         continue;
                     }
 
-                    if c == '\'' {
+                    if c == '\'' || c == '\n' {
                         break;
                     }
 
                     self.cursor += 1;
                 }
+                if !self.source[self.cursor..].starts_with('\'') {
+                    let token = self.new_token(
+                        TokenKind::Invalid("unclosed char literal"),
+                        start,
+                        self.cursor,
+                    );
+                    tokens.push(token);
+                    continue;
+                }
 
-                tokens.push(Token {
-                    kind: TokenKind::Char(
-                        unescape(&self.source[(start + 1)..self.cursor])
-                            .unwrap_or_else(|(p1, p2)| {
-                                self.source_map.error_at(
-                                    &Span {
-                                        lo: start + p1,
-                                        hi: start + p2,
-                                    },
-                                    "failed to unescape",
-                                )
-                            })
-                            .chars()
-                            .next()
-                            .unwrap(),
-                    ),
-                    span: Span {
-                        lo: start,
-                        hi: (self.cursor + 1),
-                    },
-                });
+                let c = unescape(&self.source[(start + 1)..self.cursor])
+                    .unwrap_or_else(|(p1, p2)| {
+                        self.error_at(start + 1 + p1, start + 1 + p2, "failed to unescape")
+                    })
+                    .chars()
+                    .next()
+                    .unwrap();
+                let token = self.new_token(TokenKind::Char(c), start, self.cursor + 1);
+                tokens.push(token);
 
                 self.cursor += 1;
                 continue;
@@ -244,33 +312,162 @@ impl<'src> Lexer<'src> {
                     self.cursor += 1;
                 }
 
-                tokens.push(Token {
-                    kind: TokenKind::Ident,
-                    span: Span {
-                        lo: start,
-                        hi: self.cursor,
-                    },
-                });
+                let token = self.new_token(TokenKind::Ident, start, self.cursor);
+                tokens.push(token);
                 continue;
             }
 
-            self.source_map.error_at(
-                &Span {
-                    lo: self.cursor,
-                    hi: self.cursor,
-                },
-                "トークナイズできません",
-            )
+            let start = self.cursor;
+            self.cursor += c.len_utf8();
+            let token = self.new_token(
+                TokenKind::Invalid("トークナイズできません"),
+                start,
+                self.cursor,
+            );
+            tokens.push(token);
         }
 
-        tokens.push(Token {
-            kind: TokenKind::Eof,
-            span: Span {
-                lo: self.source.len(),
-                hi: self.source.len(),
-            },
-        });
+        let token = self.new_token(TokenKind::Eof, self.source.len(), self.source.len());
+        tokens.push(token);
 
         tokens
     }
+
+    // C11 6.4.8 の前処理数 (数字か "." と数字で始まり、英数字, "_", ".", "e+" などが続くもの) を読み飛ばす
+    fn skip_pp_number(&mut self) {
+        self.cursor += 1;
+        loop {
+            let rest = &self.source[self.cursor..];
+            if rest.len() >= 2
+                && rest.starts_with(['e', 'E', 'p', 'P'])
+                && rest[1..].starts_with(['+', '-'])
+            {
+                self.cursor += 2;
+            } else if rest.starts_with(|c: char| is_ident_follow(c) || c == '.') {
+                self.cursor += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn read_number(&mut self) -> TokenKind {
+        let rest = &self.source[self.cursor..];
+        if !rest.starts_with("0x") && !rest.starts_with("0X") {
+            let int_len = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            if rest[int_len..].starts_with(['.', 'e', 'E']) {
+                return self.read_float();
+            }
+        }
+
+        let (base, prefix_len) = if rest.starts_with("0x") || rest.starts_with("0X") {
+            (16, 2)
+        } else if rest.starts_with("0b") || rest.starts_with("0B") {
+            (2, 2)
+        } else if rest.starts_with('0') {
+            (8, 0)
+        } else {
+            (10, 0)
+        };
+
+        let digits_start = self.cursor + prefix_len;
+        let digits_len = self.source[digits_start..]
+            .find(|c: char| !c.is_digit(base))
+            .unwrap_or(self.source.len() - digits_start);
+        let digits = &self.source[digits_start..digits_start + digits_len];
+        let value = u64::from_str_radix(digits, base).unwrap_or_else(|_| {
+            self.error_at(
+                self.cursor,
+                digits_start + digits_len,
+                "invalid number literal",
+            )
+        });
+        self.cursor = digits_start + digits_len;
+
+        // 整数リテラルの接尾辞 (U, L, LL とその組み合わせ)
+        let suffix_len = self.source[self.cursor..]
+            .find(|c: char| !is_ident_follow(c))
+            .unwrap_or(self.source.len() - self.cursor);
+        let suffix = self.source[self.cursor..self.cursor + suffix_len].to_ascii_lowercase();
+        let (has_u, has_l) = match suffix.as_str() {
+            "" => (false, false),
+            "u" => (true, false),
+            "l" | "ll" => (false, true),
+            "ul" | "lu" | "ull" | "llu" => (true, true),
+            _ => self.error_at(
+                self.cursor,
+                self.cursor + suffix_len,
+                "invalid number suffix",
+            ),
+        };
+        self.cursor += suffix_len;
+
+        // C11 6.4.4.1 に従って、値が収まる最初の型を選ぶ
+        let (is_unsigned, is_long) = if base == 10 {
+            match (has_u, has_l) {
+                (true, true) => (true, true),
+                (false, true) => (false, true),
+                (true, false) => (true, value >> 32 != 0),
+                (false, false) => (false, value >> 31 != 0),
+            }
+        } else {
+            match (has_u, has_l) {
+                (true, true) => (true, true),
+                (false, true) => (value >> 63 != 0, true),
+                (true, false) => (true, value >> 32 != 0),
+                (false, false) if value >> 63 != 0 => (true, true),
+                (false, false) if value >> 32 != 0 => (false, true),
+                (false, false) => (value >> 31 != 0, false),
+            }
+        };
+
+        TokenKind::Num {
+            value: value as i64,
+            is_unsigned,
+            is_long,
+        }
+    }
+
+    // 10進の浮動小数点数リテラル (C11 6.4.4.2) を読む
+    fn read_float(&mut self) -> TokenKind {
+        let start = self.cursor;
+        let skip_digits = |lexer: &mut Self| {
+            while lexer.source[lexer.cursor..].starts_with(|c: char| c.is_ascii_digit()) {
+                lexer.cursor += 1;
+            }
+        };
+
+        skip_digits(self);
+        if self.source[self.cursor..].starts_with('.') {
+            self.cursor += 1;
+            skip_digits(self);
+        }
+        if self.source[self.cursor..].starts_with(['e', 'E']) {
+            self.cursor += 1;
+            if self.source[self.cursor..].starts_with(['+', '-']) {
+                self.cursor += 1;
+            }
+            skip_digits(self);
+        }
+
+        let value = self.source[start..self.cursor]
+            .parse::<f64>()
+            .unwrap_or_else(|_| self.error_at(start, self.cursor, "invalid floating constant"));
+
+        // 接尾辞 f が付いていれば float, なければ double
+        let is_double = if self.source[self.cursor..].starts_with(['f', 'F']) {
+            self.cursor += 1;
+            false
+        } else {
+            true
+        };
+
+        if self.source[self.cursor..].starts_with(is_ident_follow) {
+            self.error_at(start, self.cursor + 1, "invalid floating constant");
+        }
+
+        TokenKind::Float { value, is_double }
+    }
 }
@@ -1,21 +1,26 @@
 use core::panic;
 use std::{
-    env::args,
-    fs::{self, File},
-    io::{self, Read, Write},
+    cell::{Cell, RefCell},
+    env::{self, args},
+    fs,
+    io::{self, Write},
 };
 
 use codegen::Codegen;
-use lexer::Lexer;
 use parser::Parser;
+use preprocessor::{Preprocessor, tokenize_file};
 
-use crate::{ctype::TypedObject, lexer::Span};
+use crate::{
+    ctype::TypedObject,
+    lexer::{Span, Token, TokenKind},
+};
 
 mod codegen;
 mod ctype;
 mod escape;
 mod lexer;
 mod parser;
+mod preprocessor;
 
 pub fn log(str: &str) {
     const FILE_PATH: &str = "log.txt";
@@ -26,51 +31,175 @@ pub fn log(str: &str) {
     f.write_all(format!("{str}\n").as_bytes()).unwrap();
 }
 
-pub struct SourceMap<'src> {
+pub struct SourceFile<'src> {
+    pub name: String,
     pub source: &'src str,
+    // Span の位置はすべてのファイルを通した通し番号になっている。このファイルの先頭の位置
+    pub start: usize,
+    // #line や GCC の -E が出力する "# 行番号 "ファイル名"" で指定された行番号。行の順に並ぶ
+    pub line_markers: Vec<LineMarker>,
+}
+
+pub struct LineMarker {
+    // マーカーの次の行の、ファイルの中での行 (0 始まり)
+    pub physical_line: usize,
+    // その行の行番号とファイル名として扱うもの
+    pub line: usize,
+    pub name: String,
+}
+
+impl SourceFile<'_> {
+    // ファイルの中での行 (0 始まり) を、行マーカーを反映したファイル名と行番号 (1 始まり) にする
+    fn presumed_line(&self, physical_line: usize) -> (&str, usize) {
+        match self
+            .line_markers
+            .iter()
+            .rev()
+            .find(|marker| marker.physical_line <= physical_line)
+        {
+            Some(marker) => (
+                &marker.name,
+                marker.line + physical_line - marker.physical_line,
+            ),
+            None => (&self.name, physical_line + 1),
+        }
+    }
+}
+
+// files の中で pos を含むものの添字。files は先頭の位置の順に並んでいる
+fn file_index(files: &[SourceFile], pos: usize) -> Option<usize> {
+    files
+        .partition_point(|file| file.start <= pos)
+        .checked_sub(1)
+}
+
+#[derive(Default)]
+pub struct SourceMap<'src> {
+    files: RefCell<Vec<SourceFile<'src>>>,
+    // マクロ展開の # や ## などで作ったトークンの字句。ファイルとは別に並べておき、
+    // 展開の回数が増えてもファイルの一覧が大きくならないようにする
+    scratch: RefCell<Vec<SourceFile<'src>>>,
+    // 次に登録するものの先頭の位置
+    next_start: Cell<usize>,
 }
 
 impl<'src> SourceMap<'src> {
-    pub fn new(source: &'src str) -> Self {
-        Self { source }
+    // ファイルを登録して、その先頭の位置を返す
+    pub fn add_file(&self, name: String, source: &'src str) -> usize {
+        self.add(&self.files, name, source)
+    }
+
+    // マクロ展開で作ったトークンの字句を登録して、その先頭の位置を返す
+    pub fn add_scratch(&self, source: &'src str) -> usize {
+        self.add(&self.scratch, "<scratch>".to_string(), source)
+    }
+
+    // 各ファイルの Eof トークンが次のファイルと重ならないように 1 つ空けておく
+    fn add(&self, list: &RefCell<Vec<SourceFile<'src>>>, name: String, source: &'src str) -> usize {
+        let start = self.next_start.get();
+        self.next_start.set(start + source.len() + 1);
+        list.borrow_mut().push(SourceFile {
+            name,
+            source,
+            start,
+            line_markers: vec![],
+        });
+        start
+    }
+
+    // pos を含むファイル (またはマクロ展開で作った字句) を f に渡す
+    fn with_file<R>(&self, pos: usize, f: impl FnOnce(&mut SourceFile<'src>) -> R) -> R {
+        let mut files = self.files.borrow_mut();
+        let mut scratch = self.scratch.borrow_mut();
+        let file = match (file_index(&files, pos), file_index(&scratch, pos)) {
+            (Some(i), Some(j)) if scratch[j].start > files[i].start => &mut scratch[j],
+            (Some(i), _) => &mut files[i],
+            (None, Some(j)) => &mut scratch[j],
+            (None, None) => panic!("no source file"),
+        };
+        f(file)
+    }
+
+    // span がある行の次の行から、行番号を line に、ファイル名を name (None なら今のまま) にする
+    pub fn add_line_marker(&self, span: &Span, line: usize, name: Option<String>) {
+        self.with_file(span.lo, |file| {
+            let physical_line = file.source[..span.lo - file.start].matches('\n').count() + 1;
+            let name = name.unwrap_or_else(|| file.presumed_line(physical_line).0.to_string());
+            file.line_markers.push(LineMarker {
+                physical_line,
+                line,
+                name,
+            });
+        });
+    }
+
+    pub fn file_name(&self, span: &Span) -> String {
+        self.with_file(span.lo, |file| file.name.clone())
+    }
+
+    // span の位置を、行マーカーを反映したファイル名と行番号、列番号にする。行と列は 1 始まり
+    pub fn location(&self, span: &Span) -> (String, usize, usize) {
+        self.with_file(span.lo, |file| {
+            let before = &file.source[..span.lo - file.start];
+            let physical_line = before.matches('\n').count();
+            let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+            let (name, line) = file.presumed_line(physical_line);
+            (name.to_string(), line, column)
+        })
     }
 
     pub fn span_to_str(&self, span: &Span) -> &'src str {
         // TODO: ここで範囲外の場合をハンドル
-        &self.source[span.lo..span.hi]
+        // ファイル名は使わないので、複製せずに中身だけを取り出す
+        let (source, start) = self.with_file(span.lo, |file| (file.source, file.start));
+        &source[span.lo - start..span.hi - start]
     }
 
     pub fn error_at(&self, span: &Span, message: &str) -> ! {
-        let lines = self.source.split("\n").collect::<Vec<_>>();
-
-        let mut line = 0;
-        let mut prev_cursor = 0;
-        let mut start = 0;
-
-        for (i, l) in lines.iter().enumerate() {
-            let end = start + l.len();
-            if span.lo <= end {
-                line = i;
-                prev_cursor = start;
-                break;
+        panic!("{}\n", self.format_at(span, message, "1;31"));
+    }
+
+    pub fn warn_at(&self, span: &Span, message: &str) {
+        eprintln!("{}", self.format_at(span, message, "1;35"));
+    }
+
+    // span の前後の行を表示して、span の下に message を添える。color はその部分の色
+    fn format_at(&self, span: &Span, message: &str, color: &str) -> String {
+        let (name, line_number, column) = self.location(span);
+        self.with_file(span.lo, |file| {
+            let (source, file_start) = (file.source, file.start);
+            let span = &Span {
+                lo: span.lo - file_start,
+                hi: span.hi - file_start,
+            };
+            let lines = source.split("\n").collect::<Vec<_>>();
+
+            let mut line = 0;
+            let mut prev_cursor = 0;
+            let mut start = 0;
+
+            for (i, l) in lines.iter().enumerate() {
+                let end = start + l.len();
+                if span.lo <= end {
+                    line = i;
+                    prev_cursor = start;
+                    break;
+                }
+                start = end + 1;
             }
-            start = end + 1;
-        }
 
-        let start_line = line.saturating_sub(2);
-        let end_line = (line + 2).min(lines.len().saturating_sub(1));
+            let start_line = line.saturating_sub(2);
+            let end_line = (line + 2).min(lines.len().saturating_sub(1));
 
-        panic!(
-            "{}\n",
-            (start_line..=end_line)
+            let snippet = (start_line..=end_line)
                 .map(|i| {
                     format!(
                         "\x1b[36m{:3} | \x1b[m{}\n{}",
-                        i + 1,
+                        file.presumed_line(i).1,
                         lines[i],
                         if line == i {
                             format!(
-                                "\x1b[36m    | \x1b[1;31m{}{} {message}\x1b[m\n",
+                                "\x1b[36m    | \x1b[{color}m{}{} {message}\x1b[m\n",
                                 " ".repeat(span.lo - prev_cursor),
                                 "^".repeat(usize::max(span.hi - span.lo, 1)),
                             )
@@ -79,52 +208,117 @@ impl<'src> SourceMap<'src> {
                         }
                     )
                 })
-                .collect::<String>(),
-        );
+                .collect::<String>();
+            format!("\x1b[1m{name}:{line_number}:{column}:\x1b[m\n{snippet}")
+        })
     }
 }
 
+// --help なら標準出力に、使い方の誤りなら標準エラー出力に出す
 fn print_usage(code: i32) -> ! {
-    println!("Usage: gakicc [ -o <PATH> ] <FILE>");
+    let usage = "Usage: gakicc [ -o <PATH> ] [ -E ] [ -dM ] [ -I <DIR> ] <FILE>";
+    if code == 0 {
+        println!("{usage}");
+    } else {
+        eprintln!("{usage}");
+    }
     std::process::exit(code);
 }
 
 struct CompileOptions<'cmd> {
     input_path: &'cmd str,
     output_path: Option<&'cmd str>,
+    include_paths: Vec<&'cmd str>,
+    // プリプロセスだけして結果を出力する
+    preprocess_only: bool,
+    // -E と一緒に指定されたら、結果の代わりに定義されているマクロを出力する
+    dump_macros: bool,
 }
 
 fn parse_args<'cmd>(args: &'cmd [String]) -> CompileOptions<'cmd> {
     let mut input_path: Option<&str> = None;
     let mut output_path: Option<&str> = None;
+    let mut include_paths = vec![];
+    let mut preprocess_only = false;
+    let mut dump_macros = false;
 
-    for i in 0..args.len() {
+    let mut i = 1;
+    while i < args.len() {
         if args[i] == "--help" {
             print_usage(0);
         }
 
-        if args[i] == "-o" {
-            output_path = Some(&args[i + 1]);
+        if args[i] == "-o" || args[i] == "-I" {
+            let Some(value) = args.get(i + 1) else {
+                print_usage(1);
+            };
+            if args[i] == "-o" {
+                output_path = Some(value);
+            } else {
+                include_paths.push(value.as_str());
+            }
+            i += 2;
             continue;
         }
 
-        if args[i].starts_with("-o") {
-            output_path = Some(&args[i][2..]);
-            continue;
+        if let Some(path) = args[i].strip_prefix("-o") {
+            output_path = Some(path);
+        } else if let Some(path) = args[i].strip_prefix("-I") {
+            include_paths.push(path);
+        } else if args[i] == "-E" {
+            preprocess_only = true;
+        } else if args[i] == "-dM" {
+            dump_macros = true;
+        } else {
+            input_path = Some(&args[i]);
         }
-
-        input_path = Some(&args[i]);
+        i += 1;
     }
 
     match input_path {
         Some(input_path) => CompileOptions {
             input_path,
             output_path,
+            include_paths,
+            preprocess_only,
+            dump_macros,
         },
-        None => panic!("no input files"),
+        None => {
+            eprintln!("gakicc: no input files");
+            print_usage(1);
+        }
     }
 }
 
+// 同梱のヘッダ (stdarg.h など) は実行ファイルと同じディレクトリの include に置く
+fn bundled_include_path() -> Option<String> {
+    let exe = env::current_exe().ok()?;
+    Some(exe.parent()?.join("include").to_string_lossy().into_owned())
+}
+
+// -I で指定したもの、同梱のヘッダの後に探すディレクトリ
+const DEFAULT_INCLUDE_PATHS: [&str; 3] = [
+    "/usr/local/include",
+    "/usr/riscv64-linux-gnu/include",
+    "/usr/include",
+];
+
+// プリプロセス後のトークン列を、行や空白を元のソースに合わせて出力する
+fn print_tokens(source_map: &SourceMap, tokens: &[Token], writer: &mut Box<dyn Write>) {
+    for (i, token) in tokens.iter().enumerate() {
+        if token.kind == TokenKind::Eof {
+            break;
+        }
+        if i > 0 && token.at_bol {
+            writeln!(writer).unwrap();
+        } else if i > 0 && token.has_space {
+            write!(writer, " ").unwrap();
+        }
+        write!(writer, "{}", source_map.span_to_str(&token.span)).unwrap();
+    }
+    writeln!(writer).unwrap();
+}
+
 fn get_writer(path: Option<&str>) -> Box<dyn Write> {
     if let Some(path) = path
         && path != "-"
@@ -147,19 +341,29 @@ fn main() {
 
     let options = parse_args(&args);
 
-    let mut source = String::new();
-    if options.input_path == "-" {
-        io::stdin().read_to_string(&mut source).unwrap();
-    } else {
-        let mut file = File::open(options.input_path)
-            .unwrap_or_else(|_| panic!("failed to open {}", options.input_path));
-        file.read_to_string(&mut source).unwrap();
-    }
+    let source_map = SourceMap::default();
+    let tokens = tokenize_file(&source_map, options.input_path)
+        .unwrap_or_else(|| panic!("failed to open {}", options.input_path));
 
-    let source_map = SourceMap::new(&source);
+    let include_paths = options
+        .include_paths
+        .iter()
+        .map(|path| path.to_string())
+        .chain(bundled_include_path())
+        .chain(DEFAULT_INCLUDE_PATHS.map(str::to_string))
+        .collect();
+    let mut preprocessor = Preprocessor::new(&source_map, include_paths);
+    let tokens = preprocessor.preprocess(tokens);
 
-    let mut lexer = Lexer::new(&source_map, &source);
-    let tokens = lexer.lex();
+    if options.preprocess_only {
+        let mut out = get_writer(options.output_path);
+        if options.dump_macros {
+            preprocessor.print_macros(&mut out);
+        } else {
+            print_tokens(&source_map, &tokens, &mut out);
+        }
+        return;
+    }
 
     let mut parser = Parser::new(&source_map, tokens);
 
@@ -3,16 +3,24 @@ use std::rc::Rc;
 use crate::{
     SourceMap,
     codegen::align_to,
-    ctype::{CType, CTypeKind, CTypeRef, TypedNode, array_of},
+    ctype::{CType, CTypeKind, CTypeRef, TypedNode, TypedNodeKind, array_of},
     lexer::{Token, TokenKind},
 };
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Object<'src> {
     Object {
+        // 同名の変数やコンパイラが作る一時変数を区別するための通し番号
+        id: usize,
         name: &'src str,
         ctype: CTypeRef<'src>,
         is_local: bool,
+        is_static: bool,
+        // extern 宣言されただけの変数は false
+        is_definition: bool,
+        // 初期化子のあるグローバル変数の中身
+        init_data: Option<Vec<InitData>>,
     },
     StringLiteral {
         id: usize,
@@ -21,10 +29,12 @@ pub enum Object<'src> {
     },
     Function {
         name: &'src str,
+        is_static: bool,
         node: Option<Node<'src>>,
         locals: Vec<Object<'src>>,
         params: Vec<Object<'src>>,
         ret_type: CTypeRef<'src>,
+        is_variadic: bool,
     },
 }
 
@@ -38,6 +48,15 @@ impl<'src> Object<'src> {
     }
 }
 
+// グローバル変数の初期値として .data に並べるもの
+#[derive(Debug, PartialEq, Clone)]
+pub enum InitData {
+    Zero(usize),
+    Int { size: usize, value: i64 },
+    // 他のグローバル変数や文字列リテラルのアドレス + addend
+    Label { label: String, addend: i64 },
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum BinOp {
     Add,
@@ -53,19 +72,48 @@ pub enum BinOp {
     Comma,
     LogAnd,
     LogOr,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum NodeKind<'src> {
-    Num(i32),
+    Num(i64),
+    FNum(f64),
     ExprStmt(Box<Node<'src>>),
     Var(Box<Object<'src>>),
     Return(Option<Box<Node<'src>>>),
+    Break,
+    Continue,
+    Switch {
+        cond: Box<Node<'src>>,
+        then: Box<Node<'src>>,
+        // case ラベルの値。Case の index はこの添字
+        cases: Vec<i64>,
+        has_default: bool,
+    },
+    Case {
+        index: usize,
+        body: Box<Node<'src>>,
+    },
+    Default(Box<Node<'src>>),
+    Goto(&'src str),
+    Label {
+        name: &'src str,
+        body: Box<Node<'src>>,
+    },
     Block(Vec<Node<'src>>),
     FuncCall {
         name: &'src str,
         args: Vec<Node<'src>>,
         ret_ty: CTypeRef<'src>,
+        // 可変長引数の関数の場合、何番目の引数から可変長部分か
+        variadic_from: Option<usize>,
+        // 構造体を返す関数の場合、戻り値を置いておく一時変数
+        ret_buf: Option<Box<Object<'src>>>,
     },
     Addr(Box<Node<'src>>),
     Deref(Box<Node<'src>>),
@@ -80,6 +128,16 @@ pub enum NodeKind<'src> {
         inc: Option<Box<Node<'src>>>,
         then: Box<Node<'src>>,
     },
+    Do {
+        then: Box<Node<'src>>,
+        cond: Box<Node<'src>>,
+    },
+    // 条件演算子 cond ? then : els
+    Cond {
+        cond: Box<Node<'src>>,
+        then: Box<Node<'src>>,
+        els: Box<Node<'src>>,
+    },
     BinOp {
         op: BinOp,
         lhs: Box<Node<'src>>,
@@ -89,16 +147,24 @@ pub enum NodeKind<'src> {
         member: Member<'src>,
         node: Box<Node<'src>>,
     },
+    Cast {
+        node: Box<Node<'src>>,
+        ctype: CTypeRef<'src>,
+    },
+    // va_start(ap, last)
+    VaStart(Box<Node<'src>>),
+    // ローカル変数の中身を 0 で埋める
+    MemZero(Box<Node<'src>>),
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Member<'src> {
     pub ty: CTypeRef<'src>,
     pub name: &'src str,
     pub offset: usize,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Node<'src> {
     pub kind: NodeKind<'src>,
 }
@@ -109,11 +175,142 @@ impl<'src> Node<'src> {
     }
 }
 
+// case ラベルの値を、レジスタ上での switch の条件式の値と同じ表現にする。
+// int より小さい型は int に格上げされ、32 bit の値は自身の符号に従って拡張されている
+fn normalize_case_value(value: i64, cond_ty: &CType) -> i64 {
+    match (cond_ty.size, cond_ty.is_unsigned) {
+        (4, true) => value as u32 as i64,
+        (8, _) => value,
+        _ => value as i32 as i64,
+    }
+}
+
+// 定数式の値を、整数型 ty の大きさと符号で表せる値に切り詰める
+fn wrap_to_type(value: i64, ty: &CType) -> i64 {
+    if !ty.is_integer() {
+        return value;
+    }
+    match (ty.size, ty.is_unsigned) {
+        (1, false) => value as i8 as i64,
+        (1, true) => value as u8 as i64,
+        (2, false) => value as i16 as i64,
+        (2, true) => value as u16 as i64,
+        (4, false) => value as i32 as i64,
+        (4, true) => value as u32 as i64,
+        _ => value,
+    }
+}
+
+fn aggregate_kind<'src>(
+    members: Vec<Member<'src>>,
+    is_incomplete: bool,
+    is_union: bool,
+) -> CTypeKind<'src> {
+    if is_union {
+        CTypeKind::Union {
+            members,
+            is_incomplete,
+        }
+    } else {
+        CTypeKind::Struct {
+            members,
+            is_incomplete,
+        }
+    }
+}
+
 pub struct Tag<'src> {
     name: &'src str,
     ty: CTypeRef<'src>,
 }
 
+// ブロックスコープを持つ識別子。変数は typedef 名や列挙定数を隠すためだけに記録する
+enum ScopedIdentKind<'src> {
+    Typedef(CTypeRef<'src>),
+    EnumConst(i64),
+    Var,
+    // ブロック内の extern 宣言が参照するグローバル変数。そのブロックの中でだけ見える
+    Extern(Object<'src>),
+}
+
+struct ScopedIdent<'src> {
+    name: &'src str,
+    kind: ScopedIdentKind<'src>,
+}
+
+// 変数の初期化子。配列、構造体、union は要素やメンバごとの初期化子を持つ
+#[derive(Clone)]
+struct Initializer<'src> {
+    ty: CTypeRef<'src>,
+    // スカラー、または構造体の値をまとめて与える場合の式
+    expr: Option<Node<'src>>,
+    children: Vec<Initializer<'src>>,
+    // 長さが省略された配列で、初期化子の数から長さを決めるもの
+    is_flexible: bool,
+    // union で初期化するメンバの添字
+    union_member: Option<usize>,
+}
+
+impl<'src> Initializer<'src> {
+    fn new(ty: CTypeRef<'src>, is_flexible: bool) -> Self {
+        let mut init = Initializer {
+            ty: Rc::clone(&ty),
+            expr: None,
+            children: vec![],
+            is_flexible: false,
+            union_member: None,
+        };
+
+        match &ty.borrow().kind {
+            CTypeKind::Array {
+                is_incomplete: true,
+                ..
+            } if is_flexible => init.is_flexible = true,
+            CTypeKind::Array { base, len, .. } => {
+                init.children = (0..*len)
+                    .map(|_| Initializer::new(Rc::clone(base), false))
+                    .collect();
+            }
+            CTypeKind::Struct { members, .. } | CTypeKind::Union { members, .. } => {
+                init.children = members
+                    .iter()
+                    .map(|member| Initializer::new(Rc::clone(&member.ty), false))
+                    .collect();
+            }
+            _ => {}
+        }
+
+        init
+    }
+
+    // 長さが省略された配列の要素を、読んだ初期化子に合わせて len 個まで増やす
+    fn extend_flexible(&mut self, len: usize) {
+        if !self.is_flexible || self.children.len() >= len {
+            return;
+        }
+        let CTypeKind::Array { base, .. } = &self.ty.borrow().kind else {
+            unreachable!()
+        };
+        self.children
+            .resize_with(len, || Initializer::new(Rc::clone(base), false));
+    }
+}
+
+// 解析中の switch 文に出てきた case ラベル
+struct SwitchCases<'src> {
+    cond_ty: CTypeRef<'src>,
+    values: Vec<i64>,
+    has_default: bool,
+}
+
+// 宣言に付く記憶域クラス指定子
+#[derive(Default)]
+struct VarAttr {
+    is_typedef: bool,
+    is_static: bool,
+    is_extern: bool,
+}
+
 pub struct Parser<'src> {
     source_map: &'src SourceMap<'src>,
     tokens: Vec<Token>,
@@ -121,7 +318,23 @@ pub struct Parser<'src> {
     locals: Vec<Object<'src>>,
     pub globals: Vec<Object<'src>>,
     tags: Vec<Tag<'src>>,
+    scope: Vec<ScopedIdent<'src>>,
+    // ブロックに入った時点の scope と tags の長さ。ブロックを抜ける時にここまで戻す
+    scope_marks: Vec<(usize, usize)>,
     anon_gvar_count: usize,
+    var_count: usize,
+    current_ret_ty: Option<CTypeRef<'src>>,
+    // 解析中の関数が可変長引数をとるか。va_start を使えるかの判定に使う
+    current_is_variadic: bool,
+    // 解析中の文を囲む break, continue できる文の数
+    brk_depth: usize,
+    cont_depth: usize,
+    current_switch: Option<SwitchCases<'src>>,
+    // 解析中の関数に出てきたラベルと goto。goto は関数の最後に飛び先があるか確かめる
+    labels: Vec<&'src str>,
+    gotos: Vec<(&'src str, usize)>,
+    // 括弧を省略した構造体の初期化子で、構造体の値かどうか確かめるために先に読んだ式。最初のスカラーの要素に使う
+    pending_init: Option<Node<'src>>,
 }
 
 impl<'src> Parser<'src> {
@@ -133,7 +346,22 @@ impl<'src> Parser<'src> {
             locals: vec![],
             globals: vec![],
             tags: vec![],
+            // va_list の実体は引数の退避領域を指すポインタ
+            scope: vec![ScopedIdent {
+                name: "__builtin_va_list",
+                kind: ScopedIdentKind::Typedef(CType::pointer_to(CType::void())),
+            }],
+            scope_marks: vec![],
             anon_gvar_count: 0,
+            var_count: 0,
+            current_ret_ty: None,
+            current_is_variadic: false,
+            brk_depth: 0,
+            cont_depth: 0,
+            current_switch: None,
+            labels: vec![],
+            gotos: vec![],
+            pending_init: None,
         }
     }
 
@@ -156,36 +384,51 @@ impl<'src> Parser<'src> {
         }
     }
 
-    pub fn expect_number(&mut self) -> i32 {
-        let token = &self.tokens[self.cursor];
-        if let TokenKind::Num(value) = token.kind {
-            self.cursor += 1;
-            value
-        } else {
-            self.error_at("数ではありません");
-        }
-    }
-
     pub fn at_eof(&self) -> bool {
         self.tokens[self.cursor].kind == TokenKind::Eof
     }
 
     pub fn error_at(&self, message: &str) -> ! {
-        let error_span = &self.tokens[self.cursor].span;
+        let error_span = self.tokens[self.cursor].error_span();
         self.source_map.error_at(error_span, message)
     }
 
     fn new_var(&mut self, name: &'src str, ctype: CTypeRef<'src>, is_local: bool) -> Object<'src> {
+        self.new_var_with_attr(name, ctype, is_local, &VarAttr::default())
+    }
+
+    // 関数内の static 変数はグローバル変数としても登録して、.data に置かれるようにする
+    fn new_var_with_attr(
+        &mut self,
+        name: &'src str,
+        ctype: CTypeRef<'src>,
+        is_local: bool,
+        attr: &VarAttr,
+    ) -> Object<'src> {
+        // 同名の typedef 名や列挙定数があればこの変数で隠す
+        if self.find_scoped_ident(name).is_some() {
+            self.scope.push(ScopedIdent {
+                name,
+                kind: ScopedIdentKind::Var,
+            });
+        }
+
         let obj = Object::Object {
+            id: self.var_count,
             name,
             ctype,
             is_local,
+            is_static: attr.is_static,
+            is_definition: !attr.is_extern,
+            init_data: None,
         };
+        self.var_count += 1;
 
         // TODO: ここどっちか参照にできない？
         if is_local {
             self.locals.push(obj.clone());
-        } else {
+        }
+        if !is_local || attr.is_static {
             self.globals.push(obj.clone());
         }
 
@@ -207,16 +450,31 @@ impl<'src> Parser<'src> {
     fn create_param_lvars(&mut self, ctype: CTypeRef<'src>) {
         if let CTypeKind::Function { params, .. } = &ctype.borrow().kind {
             for param in params {
-                let name = self.get_ident(param.borrow().name.clone().unwrap());
+                // 名前の省略はプロトタイプ宣言でのみ許される
+                let name = match param.borrow().name.clone() {
+                    Some(token) => self.get_ident(token),
+                    None if self.is_equal(";") => "",
+                    None => self.error_at("parameter name omitted"),
+                };
                 self.new_var(name, param.to_owned(), true);
             }
         }
     }
 
     fn find_var(&self, name: &str) -> Option<Object<'src>> {
+        if let Some(ScopedIdentKind::Extern(obj)) = self.find_scoped_ident(name) {
+            return Some(obj.clone());
+        }
+
         self.locals
             .iter()
-            .chain(self.globals.iter().rev())
+            .chain(
+                // 関数内の static 変数は locals から探す
+                self.globals
+                    .iter()
+                    .rev()
+                    .filter(|obj| !matches!(obj, Object::Object { is_local: true, .. })),
+            )
             .find(|obj| obj.name() == Some(name))
             .cloned()
     }
@@ -228,81 +486,192 @@ impl<'src> Parser<'src> {
 
         let cursor = self.cursor;
         let dummy = CType::dummy();
-        let decl = self.declarator(dummy);
+        let (decl, _) = self.declarator(dummy);
         let ty = &decl.borrow().kind;
         self.cursor = cursor;
 
         matches!(ty, CTypeKind::Function { .. })
     }
 
+    fn enter_scope(&mut self) {
+        self.scope_marks.push((self.scope.len(), self.tags.len()));
+    }
+
+    fn leave_scope(&mut self) {
+        let (scope_len, tags_len) = self.scope_marks.pop().unwrap();
+        self.scope.truncate(scope_len);
+        self.tags.truncate(tags_len);
+    }
+
+    fn find_scoped_ident(&self, name: &str) -> Option<&ScopedIdentKind<'src>> {
+        self.scope
+            .iter()
+            .rev()
+            .find(|ident| ident.name == name)
+            .map(|ident| &ident.kind)
+    }
+
+    fn find_typedef(&self, name: &str) -> Option<CTypeRef<'src>> {
+        match self.find_scoped_ident(name) {
+            Some(ScopedIdentKind::Typedef(ty)) => Some(Rc::clone(ty)),
+            _ => None,
+        }
+    }
+
+    fn find_enum_const(&self, name: &str) -> Option<i64> {
+        match self.find_scoped_ident(name) {
+            Some(ScopedIdentKind::EnumConst(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn current_typedef(&self) -> Option<CTypeRef<'src>> {
+        let token = &self.tokens[self.cursor];
+        if token.kind != TokenKind::Ident {
+            return None;
+        }
+
+        self.find_typedef(self.source_map.span_to_str(&token.span))
+    }
+
     fn is_typename(&mut self) -> bool {
         self.is_equal("void")
             || self.is_equal("int")
             || self.is_equal("char")
+            || self.is_equal("_Bool")
+            || self.is_equal("short")
+            || self.is_equal("long")
+            || self.is_equal("signed")
+            || self.is_equal("unsigned")
+            || self.is_equal("float")
+            || self.is_equal("double")
             || self.is_equal("struct")
+            || self.is_equal("union")
+            || self.is_equal("enum")
             || self.is_equal("const")
+            || self.is_equal("typedef")
+            || self.is_equal("static")
+            || self.is_equal("extern")
+            || self.current_typedef().is_some()
     }
 
     pub fn parse(&mut self) -> Vec<Object<'src>> {
         while !self.at_eof() {
-            let basety = self.declspec();
+            let mut attr = VarAttr::default();
+            let basety = self.declspec(Some(&mut attr));
+
+            if attr.is_typedef {
+                self.parse_typedef(basety);
+                continue;
+            }
 
             if self.is_function() {
-                self.function(basety);
+                self.function(basety, &attr);
             } else {
-                self.global_variable(basety);
+                self.global_variable(basety, &attr);
+            }
+        }
+
+        // 最後まで長さが決まらなかった配列の仮定義は、要素が 1 つの配列として定義する
+        for obj in &mut self.globals {
+            if let Object::Object {
+                ctype,
+                is_local: false,
+                is_definition: true,
+                ..
+            } = obj
+            {
+                let base = match &ctype.borrow().kind {
+                    CTypeKind::Array {
+                        base,
+                        is_incomplete: true,
+                        ..
+                    } => Rc::clone(base),
+                    _ => continue,
+                };
+                *ctype = array_of(base, 1);
             }
         }
 
         self.globals.clone()
     }
 
-    fn function(&mut self, basety: CTypeRef<'src>) {
-        let ty = self.declarator(basety);
-        let ret_ty = match &ty.borrow().kind {
-            CTypeKind::Function { return_ty, .. } => Rc::clone(return_ty),
+    fn function(&mut self, basety: CTypeRef<'src>, attr: &VarAttr) {
+        let (ty, name) = self.declarator(basety);
+        let (ret_ty, is_variadic) = match &ty.borrow().kind {
+            CTypeKind::Function {
+                return_ty,
+                is_variadic,
+                ..
+            } => (Rc::clone(return_ty), *is_variadic),
             _ => self.error_at("not a function"),
         };
 
         self.locals = vec![];
-        let name = self.get_ident(ty.borrow().name.clone().unwrap());
+        let name = self.get_ident(name);
+        self.enter_scope();
         self.create_param_lvars(Rc::clone(&ty));
         let params = self.locals.clone();
 
-        let idx = if let Some(i) = self.globals.iter().position(|g| g.name() == Some(name)) {
+        if self.find_global_var(name).is_some() {
+            self.error_at(&format!("'{name}' redeclared as different kind of symbol"));
+        }
+        let idx = if let Some(i) = self.find_function(name) {
+            if let Object::Function { node: Some(_), .. } = self.globals[i]
+                && self.is_equal("{")
+            {
+                self.error_at(&format!("redefinition of '{name}'"));
+            }
             i
         } else {
             self.globals.push(Object::Function {
                 name,
+                is_static: attr.is_static,
                 node: None,
                 locals: vec![],
                 params: params.clone(),
                 ret_type: ret_ty.clone(),
+                is_variadic,
             });
             self.globals.len() - 1
         };
 
+        // 一度 static で宣言された関数は内部結合のまま
+        if let Object::Function { is_static, .. } = &mut self.globals[idx] {
+            *is_static |= attr.is_static;
+        }
+
         if self.consume(";") {
             if let Object::Function {
                 ret_type,
                 params: p,
+                is_variadic: v,
                 ..
             } = &mut self.globals[idx]
             {
                 *ret_type = ret_ty;
                 *p = params;
+                *v = is_variadic;
             }
+            self.leave_scope();
             return;
         }
 
         self.expect("{");
+        self.current_ret_ty = Some(Rc::clone(&ret_ty));
+        self.current_is_variadic = is_variadic;
+        self.labels = vec![];
+        self.gotos = vec![];
         let body = self.compound_stmt();
+        self.leave_scope();
+        self.resolve_gotos();
 
         if let Object::Function {
             node,
             locals,
             params: p,
             ret_type,
+            is_variadic: v,
             ..
         } = &mut self.globals[idx]
         {
@@ -310,10 +679,20 @@ impl<'src> Parser<'src> {
             *locals = self.locals.clone();
             *p = params;
             *ret_type = ret_ty;
+            *v = is_variadic;
+        }
+    }
+
+    fn resolve_gotos(&mut self) {
+        for &(name, cursor) in &self.gotos {
+            if !self.labels.contains(&name) {
+                self.cursor = cursor;
+                self.error_at(&format!("use of undeclared label '{name}'"));
+            }
         }
     }
 
-    fn global_variable(&mut self, basety: CTypeRef<'src>) {
+    fn global_variable(&mut self, basety: CTypeRef<'src>, attr: &VarAttr) {
         let mut is_first = true;
 
         while !self.consume(";") {
@@ -322,16 +701,220 @@ impl<'src> Parser<'src> {
             }
             is_first = false;
 
-            let ty = self.declarator(Rc::clone(&basety));
-            let span = &ty.borrow().name.clone().unwrap().span;
-            self.new_var(self.source_map.span_to_str(span), ty, false);
+            let (ty, name) = self.declarator(Rc::clone(&basety));
+            let name = self.source_map.span_to_str(&name.span);
+            if self.find_function(name).is_some() {
+                self.error_at(&format!("'{name}' redeclared as different kind of symbol"));
+            }
+            let obj = match self.find_global_var(name) {
+                Some(idx) => self.redeclare_global_var(idx, ty, attr),
+                None => self.new_var_with_attr(name, ty, false, attr),
+            };
+
+            if self.consume("=") {
+                if let Object::Object {
+                    init_data: Some(_), ..
+                } = obj
+                {
+                    self.error_at(&format!("redefinition of '{name}'"));
+                }
+                self.global_initializer(obj);
+            }
+        }
+    }
+
+    fn find_global_var(&self, name: &str) -> Option<usize> {
+        self.globals.iter().rposition(|obj| {
+            matches!(
+                obj,
+                Object::Object {
+                    is_local: false,
+                    ..
+                }
+            ) && obj.name() == Some(name)
+        })
+    }
+
+    fn find_function(&self, name: &str) -> Option<usize> {
+        self.globals
+            .iter()
+            .position(|obj| matches!(obj, Object::Function { .. }) && obj.name() == Some(name))
+    }
+
+    // 同じグローバル変数の宣言をまとめる。初期化子のない宣言 (仮定義) が何度あっても定義は一つになる
+    fn redeclare_global_var(
+        &mut self,
+        idx: usize,
+        ty: CTypeRef<'src>,
+        attr: &VarAttr,
+    ) -> Object<'src> {
+        let Object::Object {
+            name,
+            ctype,
+            is_static,
+            is_definition,
+            ..
+        } = &mut self.globals[idx]
+        else {
+            unreachable!()
+        };
+        let name = *name;
+
+        if !ctype.borrow().is_compatible(&ty.borrow()) {
+            self.error_at(&format!("conflicting types for '{name}'"));
+        }
+        // extern 宣言は先の宣言の結合を引き継ぐ。それ以外で結合が食い違う宣言はエラー
+        if attr.is_static && !*is_static {
+            self.error_at(&format!(
+                "static declaration of '{name}' follows non-static declaration"
+            ));
+        }
+        if !attr.is_static && !attr.is_extern && *is_static {
+            self.error_at(&format!(
+                "non-static declaration of '{name}' follows static declaration"
+            ));
+        }
+
+        // 長さの分かっている方の配列の型にする
+        if matches!(
+            ctype.borrow().kind,
+            CTypeKind::Array {
+                is_incomplete: true,
+                ..
+            }
+        ) {
+            *ctype = ty;
+        }
+        *is_definition |= !attr.is_extern;
+
+        self.globals[idx].clone()
+    }
+
+    // 初期化子を定数として評価して、グローバル変数や関数内の static 変数の中身を決める
+    fn global_initializer(&mut self, obj: Object<'src>) {
+        let Object::Object { id, ctype, .. } = obj else {
+            unreachable!()
+        };
+        let init = self.initializer(ctype);
+
+        let mut data = vec![];
+        let mut pos = 0;
+        self.write_global_data(&init, 0, &mut data, &mut pos);
+        let size = init.ty.borrow().size;
+        if pos < size {
+            data.push(InitData::Zero(size - pos));
+        }
+
+        // 関数内の static 変数は locals にもあるので両方書き換える
+        for obj in self.globals.iter_mut().chain(self.locals.iter_mut()) {
+            if let Object::Object {
+                id: i,
+                ctype,
+                is_definition,
+                init_data,
+                ..
+            } = obj
+                && *i == id
+            {
+                *ctype = Rc::clone(&init.ty);
+                *is_definition = true;
+                *init_data = Some(data.clone());
+            }
+        }
+    }
+
+    // offset に置く init の中身を data に追加する。pos はここまでに埋めた位置
+    fn write_global_data(
+        &self,
+        init: &Initializer<'src>,
+        offset: usize,
+        data: &mut Vec<InitData>,
+        pos: &mut usize,
+    ) {
+        let ty = init.ty.borrow();
+        if let Some(expr) = &init.expr {
+            let node: TypedNode<'src> = Node::new(NodeKind::Cast {
+                node: Box::new(expr.clone()),
+                ctype: Rc::clone(&init.ty),
+            })
+            .into();
+
+            if *pos < offset {
+                data.push(InitData::Zero(offset - *pos));
+            }
+            *pos = offset + ty.size;
+
+            if ty.is_flonum() {
+                let Some(value) = self.eval_double(&node) else {
+                    self.error_at("initializer element is not a compile-time constant");
+                };
+                let value = if ty.kind == CTypeKind::Float {
+                    i64::from((value as f32).to_bits())
+                } else {
+                    value.to_bits() as i64
+                };
+                data.push(InitData::Int {
+                    size: ty.size,
+                    value,
+                });
+                return;
+            }
+
+            let mut label = None;
+            match self.eval_with_label(&node, &mut label) {
+                Some(addend) if label.is_some() && ty.size == 8 => data.push(InitData::Label {
+                    label: label.unwrap(),
+                    addend,
+                }),
+                Some(value) if label.is_none() => data.push(InitData::Int {
+                    size: ty.size,
+                    value,
+                }),
+                _ => self.error_at("initializer element is not a compile-time constant"),
+            }
+            return;
+        }
+
+        match &ty.kind {
+            CTypeKind::Array { base, .. } => {
+                let size = base.borrow().size;
+                for (i, child) in init.children.iter().enumerate() {
+                    self.write_global_data(child, offset + i * size, data, pos);
+                }
+            }
+            CTypeKind::Struct { members, .. } => {
+                for (member, child) in members.iter().zip(&init.children) {
+                    self.write_global_data(child, offset + member.offset, data, pos);
+                }
+            }
+            CTypeKind::Union { members, .. } => {
+                if let Some(i) = init.union_member {
+                    self.write_global_data(
+                        &init.children[i],
+                        offset + members[i].offset,
+                        data,
+                        pos,
+                    );
+                }
+            }
+            _ => {}
         }
     }
 
     fn stmt(&mut self) -> Node<'src> {
         if self.consume("return") {
             if !self.consume(";") {
-                let node = Node::new(NodeKind::Return(Some(Box::new(self.expr()))));
+                let mut expr = self.expr();
+                if let Some(ret_ty) = &self.current_ret_ty
+                    && ret_ty.borrow().is_numeric()
+                {
+                    expr = Node::new(NodeKind::Cast {
+                        node: Box::new(expr),
+                        ctype: Rc::clone(ret_ty),
+                    });
+                }
+
+                let node = Node::new(NodeKind::Return(Some(Box::new(expr))));
                 self.expect(";");
                 return node;
             }
@@ -358,9 +941,11 @@ impl<'src> Parser<'src> {
 
         if self.consume("for") {
             self.expect("(");
+            self.enter_scope();
             let init = Some({
                 if self.is_typename() {
-                    self.declaration()
+                    let basety = self.declspec(None);
+                    self.declaration(basety, &VarAttr::default())
                 } else {
                     self.expr_stmt()
                 }
@@ -379,7 +964,8 @@ impl<'src> Parser<'src> {
                 self.expect(")");
             }
 
-            let then = self.stmt();
+            let then = self.loop_body();
+            self.leave_scope();
 
             return Node::new(NodeKind::For {
                 init: init.map(Box::new),
@@ -393,7 +979,7 @@ impl<'src> Parser<'src> {
             self.expect("(");
             let cond = Some(self.expr());
             self.expect(")");
-            let then = self.stmt();
+            let then = self.loop_body();
 
             return Node::new(NodeKind::For {
                 init: None,
@@ -403,6 +989,110 @@ impl<'src> Parser<'src> {
             });
         }
 
+        if self.consume("switch") {
+            return self.switch_stmt();
+        }
+
+        if self.consume("case") {
+            if self.current_switch.is_none() {
+                self.cursor -= 1;
+                self.error_at("stray case");
+            }
+
+            let start = self.cursor;
+            let value = self.const_expr();
+            self.expect(":");
+            let switch = self.current_switch.as_ref().unwrap();
+            let value = normalize_case_value(value, &switch.cond_ty.borrow());
+            if switch.values.contains(&value) {
+                self.cursor = start;
+                self.error_at("duplicate case value");
+            }
+
+            let switch = self.current_switch.as_mut().unwrap();
+            switch.values.push(value);
+            let index = switch.values.len() - 1;
+            return Node::new(NodeKind::Case {
+                index,
+                body: Box::new(self.stmt()),
+            });
+        }
+
+        if self.consume("default") {
+            match &mut self.current_switch {
+                Some(switch) if !switch.has_default => switch.has_default = true,
+                Some(_) => {
+                    self.cursor -= 1;
+                    self.error_at("multiple default labels in one switch");
+                }
+                None => {
+                    self.cursor -= 1;
+                    self.error_at("stray default");
+                }
+            }
+            self.expect(":");
+            return Node::new(NodeKind::Default(Box::new(self.stmt())));
+        }
+
+        if self.consume("goto") {
+            let cursor = self.cursor;
+            let name = self.get_ident(self.tokens[self.cursor].clone());
+            self.cursor += 1;
+            self.expect(";");
+            self.gotos.push((name, cursor));
+            return Node::new(NodeKind::Goto(name));
+        }
+
+        if self.tokens[self.cursor].kind == TokenKind::Ident
+            && self
+                .source_map
+                .span_to_str(&self.tokens[self.cursor + 1].span)
+                == ":"
+        {
+            let name = self.get_ident(self.tokens[self.cursor].clone());
+            if self.labels.contains(&name) {
+                self.error_at(&format!("redefinition of label '{name}'"));
+            }
+            self.labels.push(name);
+            self.cursor += 2;
+            return Node::new(NodeKind::Label {
+                name,
+                body: Box::new(self.stmt()),
+            });
+        }
+
+        if self.consume("break") {
+            if self.brk_depth == 0 {
+                self.cursor -= 1;
+                self.error_at("stray break");
+            }
+            self.expect(";");
+            return Node::new(NodeKind::Break);
+        }
+
+        if self.consume("continue") {
+            if self.cont_depth == 0 {
+                self.cursor -= 1;
+                self.error_at("stray continue");
+            }
+            self.expect(";");
+            return Node::new(NodeKind::Continue);
+        }
+
+        if self.consume("do") {
+            let then = self.loop_body();
+            self.expect("while");
+            self.expect("(");
+            let cond = self.expr();
+            self.expect(")");
+            self.expect(";");
+
+            return Node::new(NodeKind::Do {
+                then: Box::new(then),
+                cond: Box::new(cond),
+            });
+        }
+
         if self.consume("{") {
             return self.compound_stmt();
         }
@@ -410,6 +1100,43 @@ impl<'src> Parser<'src> {
         self.expr_stmt()
     }
 
+    fn switch_stmt(&mut self) -> Node<'src> {
+        self.expect("(");
+        let cond = self.expr();
+        let cond_ty = TypedNode::from(cond.clone()).ctype.unwrap();
+        if !cond_ty.borrow().is_integer() {
+            self.error_at("switch quantity is not an integer");
+        }
+        self.expect(")");
+
+        let outer = self.current_switch.replace(SwitchCases {
+            cond_ty,
+            values: vec![],
+            has_default: false,
+        });
+        self.brk_depth += 1;
+        let then = self.stmt();
+        self.brk_depth -= 1;
+        let switch = std::mem::replace(&mut self.current_switch, outer).unwrap();
+
+        Node::new(NodeKind::Switch {
+            cond: Box::new(cond),
+            then: Box::new(then),
+            cases: switch.values,
+            has_default: switch.has_default,
+        })
+    }
+
+    // break と continue の対象になるループの本体
+    fn loop_body(&mut self) -> Node<'src> {
+        self.brk_depth += 1;
+        self.cont_depth += 1;
+        let node = self.stmt();
+        self.brk_depth -= 1;
+        self.cont_depth -= 1;
+        node
+    }
+
     fn get_ident(&mut self, token: Token) -> &'src str {
         if token.kind != TokenKind::Ident {
             self.error_at(&format!("expected identifier, got {token:?}"));
@@ -418,34 +1145,163 @@ impl<'src> Parser<'src> {
         self.source_map.span_to_str(&token.span)
     }
 
-    fn declspec(&mut self) -> CTypeRef<'src> {
+    fn declspec(&mut self, mut attr: Option<&mut VarAttr>) -> CTypeRef<'src> {
+        // 型指定子の出現回数を数えて、その組み合わせから型を決める。
+        // 例えば "long int" と "int long" はどちらも LONG + INT になる
+        const VOID: u32 = 1 << 0;
+        const CHAR: u32 = 1 << 2;
+        const SHORT: u32 = 1 << 4;
+        const INT: u32 = 1 << 6;
+        const LONG: u32 = 1 << 8;
+        const OTHER: u32 = 1 << 10;
+        const SIGNED: u32 = 1 << 12;
+        const UNSIGNED: u32 = 1 << 14;
+        const FLOAT: u32 = 1 << 16;
+        const DOUBLE: u32 = 1 << 18;
+        const BOOL: u32 = 1 << 20;
+
+        let mut ty = None;
+        let mut counter = 0;
+
         while self.is_typename() {
             if self.consume("const") {
                 continue;
             }
 
-            if self.consume("void") {
-                return CType::new(CTypeKind::Void, None, 1, 1);
+            if self.is_equal("typedef") || self.is_equal("static") || self.is_equal("extern") {
+                let Some(attr) = attr.as_deref_mut() else {
+                    self.error_at("storage class specifier is not allowed in this context");
+                };
+                match self.source_map.span_to_str(&self.tokens[self.cursor].span) {
+                    "typedef" => attr.is_typedef = true,
+                    "static" => attr.is_static = true,
+                    _ => attr.is_extern = true,
+                }
+                if attr.is_typedef && (attr.is_static || attr.is_extern) {
+                    self.error_at("typedef may not be used together with static or extern");
+                }
+                self.cursor += 1;
+                continue;
             }
 
-            if self.consume("char") {
-                return CType::char();
+            if self.is_equal("struct") {
+                if counter != 0 {
+                    break;
+                }
+
+                self.cursor += 1;
+                ty = Some(self.struct_decl());
+                counter += OTHER;
+                continue;
             }
 
-            if self.consume("int") {
-                return CType::int();
+            if self.is_equal("union") {
+                if counter != 0 {
+                    break;
+                }
+
+                self.cursor += 1;
+                ty = Some(self.union_decl());
+                counter += OTHER;
+                continue;
             }
 
-            if self.consume("struct") {
-                return self.struct_decl();
+            if self.is_equal("enum") {
+                if counter != 0 {
+                    break;
+                }
+
+                self.cursor += 1;
+                ty = Some(self.enum_specifier());
+                counter += OTHER;
+                continue;
             }
-        }
 
-        self.error_at("typename expected");
-    }
+            if let Some(typedef) = self.current_typedef() {
+                if counter != 0 {
+                    break;
+                }
 
-    fn func_params(&mut self, ty: CTypeRef<'src>) -> CTypeRef<'src> {
+                self.cursor += 1;
+                ty = Some(typedef);
+                counter += OTHER;
+                continue;
+            }
+
+            if self.consume("void") {
+                counter += VOID;
+            } else if self.consume("char") {
+                counter += CHAR;
+            } else if self.consume("short") {
+                counter += SHORT;
+            } else if self.consume("int") {
+                counter += INT;
+            } else if self.consume("long") {
+                counter += LONG;
+            } else if self.consume("signed") {
+                counter += SIGNED;
+            } else if self.consume("unsigned") {
+                counter += UNSIGNED;
+            } else if self.consume("float") {
+                counter += FLOAT;
+            } else if self.consume("double") {
+                counter += DOUBLE;
+            } else if self.consume("_Bool") {
+                counter += BOOL;
+            } else {
+                unreachable!();
+            }
+
+            ty = Some(match counter {
+                VOID => CType::void(),
+                BOOL => CType::bool(),
+                c if [CHAR, SIGNED + CHAR].contains(&c) => CType::char(),
+                c if c == UNSIGNED + CHAR => CType::uchar(),
+                c if [SHORT, SHORT + INT, SIGNED + SHORT, SIGNED + SHORT + INT].contains(&c) => {
+                    CType::short()
+                }
+                c if [UNSIGNED + SHORT, UNSIGNED + SHORT + INT].contains(&c) => CType::ushort(),
+                c if [INT, SIGNED, SIGNED + INT].contains(&c) => CType::int(),
+                c if [UNSIGNED, UNSIGNED + INT].contains(&c) => CType::uint(),
+                c if [
+                    LONG,
+                    LONG + INT,
+                    LONG + LONG,
+                    LONG + LONG + INT,
+                    SIGNED + LONG,
+                    SIGNED + LONG + INT,
+                    SIGNED + LONG + LONG,
+                    SIGNED + LONG + LONG + INT,
+                ]
+                .contains(&c) =>
+                {
+                    CType::long()
+                }
+                c if [
+                    UNSIGNED + LONG,
+                    UNSIGNED + LONG + INT,
+                    UNSIGNED + LONG + LONG,
+                    UNSIGNED + LONG + LONG + INT,
+                ]
+                .contains(&c) =>
+                {
+                    CType::ulong()
+                }
+                FLOAT => CType::float(),
+                DOUBLE => CType::double(),
+                _ => {
+                    self.cursor -= 1;
+                    self.error_at("invalid type");
+                }
+            });
+        }
+
+        ty.unwrap_or_else(|| self.error_at("typename expected"))
+    }
+
+    fn func_params(&mut self, ty: CTypeRef<'src>) -> CTypeRef<'src> {
         let mut params = vec![];
+        let mut is_variadic = false;
         let mut is_head = true;
         while !self.consume(")") {
             if !is_head {
@@ -453,15 +1309,33 @@ impl<'src> Parser<'src> {
             }
             is_head = false;
 
-            let basety = self.declspec();
-            let ty = self.declarator(basety);
-            params.push(Rc::clone(&ty));
+            if self.consume("...") {
+                is_variadic = true;
+                self.expect(")");
+                break;
+            }
+
+            let basety = self.declspec(None);
+            let start = self.cursor;
+            let (ty, name) = self.param_declarator(basety);
+            if let CTypeKind::Void = ty.borrow().kind {
+                // 宣言子のない (void) は引数を取らないことを表す
+                if params.is_empty() && self.cursor == start && self.consume(")") {
+                    break;
+                }
+                self.error_at("parameter declared void");
+            }
+            // 仮引数の名前は型に持たせる。typedef された型は共有されているので複製してから設定する
+            let mut param = ty.borrow().clone();
+            param.name = name;
+            params.push(param.into());
         }
 
         CType::new(
             CTypeKind::Function {
                 return_ty: Box::new(ty),
                 params,
+                is_variadic,
             },
             // TODO: ここの name と size, align がこれでいいかわからない
             None,
@@ -476,7 +1350,22 @@ impl<'src> Parser<'src> {
         }
 
         if self.consume("[") {
-            let sz = self.expect_number();
+            if self.consume("]") {
+                let ty = self.type_suffix(ty);
+                let align = ty.borrow().align;
+                return CType::new(
+                    CTypeKind::Array {
+                        base: Box::new(ty),
+                        len: 0,
+                        is_incomplete: true,
+                    },
+                    None,
+                    0,
+                    align,
+                );
+            }
+
+            let sz = self.const_expr();
             self.expect("]");
             let ty = self.type_suffix(ty);
             return array_of(ty, sz as usize);
@@ -485,25 +1374,42 @@ impl<'src> Parser<'src> {
         ty
     }
 
-    fn declarator(&mut self, mut ty: CTypeRef<'src>) -> CTypeRef<'src> {
+    fn declarator(&mut self, ty: CTypeRef<'src>) -> (CTypeRef<'src>, Token) {
+        let (ty, name) = self.declarator_with_name(ty, false);
+        (ty, name.unwrap())
+    }
+
+    // 仮引数の宣言子。プロトタイプ宣言では名前を省略できるので、名前のない宣言子も受け付ける
+    fn param_declarator(&mut self, ty: CTypeRef<'src>) -> (CTypeRef<'src>, Option<Token>) {
+        self.declarator_with_name(ty, true)
+    }
+
+    fn declarator_with_name(
+        &mut self,
+        mut ty: CTypeRef<'src>,
+        may_omit_name: bool,
+    ) -> (CTypeRef<'src>, Option<Token>) {
         while self.consume("*") {
             ty = CType::pointer_to(ty);
         }
 
         if self.consume("(") {
             let start = self.cursor;
-            self.declarator(CType::dummy());
+            self.declarator_with_name(CType::dummy(), may_omit_name);
             self.expect(")");
             ty = self.type_suffix(ty);
             let after_suffix = self.cursor;
             self.cursor = start;
-            ty = self.declarator(ty);
+            let (ty, name) = self.declarator_with_name(ty, may_omit_name);
             self.cursor = after_suffix;
 
-            return ty;
+            return (ty, name);
         }
 
         if self.tokens[self.cursor].kind != TokenKind::Ident {
+            if may_omit_name {
+                return (self.type_suffix(ty), None);
+            }
             self.error_at("expected a variable name");
         }
 
@@ -513,11 +1419,8 @@ impl<'src> Parser<'src> {
         self.cursor += 1;
 
         // その後に "(" ")" が続いた場合に型を関数に変更
-        ty = self.type_suffix(ty);
-        // 名前を設定
-        ty.borrow_mut().name = name;
-
-        ty
+        // 型は typedef などと共有されていることがあるので、名前は型に書き込まずに返す
+        (self.type_suffix(ty), name)
     }
 
     fn abstract_declarator(&mut self, mut ty: CTypeRef<'src>) -> CTypeRef<'src> {
@@ -544,13 +1447,29 @@ impl<'src> Parser<'src> {
     }
 
     fn typename(&mut self) -> CTypeRef<'src> {
-        let ty = self.declspec();
+        let ty = self.declspec(None);
         self.abstract_declarator(ty)
     }
 
-    fn declaration(&mut self) -> Node<'src> {
-        let basety = self.declspec();
+    fn parse_typedef(&mut self, basety: CTypeRef<'src>) {
+        let mut is_first = true;
+
+        while !self.consume(";") {
+            if !is_first {
+                self.expect(",");
+            }
+            is_first = false;
+
+            let (ty, name) = self.declarator(Rc::clone(&basety));
+            let name = self.get_ident(name);
+            self.scope.push(ScopedIdent {
+                name,
+                kind: ScopedIdentKind::Typedef(ty),
+            });
+        }
+    }
 
+    fn declaration(&mut self, basety: CTypeRef<'src>, attr: &VarAttr) -> Node<'src> {
         let mut i = 0;
         let mut cur = vec![];
         while !self.consume(";") {
@@ -559,40 +1478,479 @@ impl<'src> Parser<'src> {
             }
             i += 1;
 
-            let ty = self.declarator(Rc::clone(&basety));
+            let (ty, name) = self.declarator(Rc::clone(&basety));
             if let CTypeKind::Void = ty.borrow().kind {
                 self.error_at("variable declared void");
             }
 
-            let name = self.get_ident(ty.borrow().name.clone().unwrap());
-            let obj = self.new_var(name, ty, true);
+            let name = self.get_ident(name);
+            if attr.is_extern {
+                if self.consume("=") {
+                    self.error_at("'extern' variable has an initializer");
+                }
+                self.block_extern(name, ty, attr);
+                continue;
+            }
+            let obj = self.new_var_with_attr(name, ty, true, attr);
 
             if !self.consume("=") {
                 continue;
             }
 
-            let lhs = Node::new(NodeKind::Var(Box::new(obj)));
-            let rhs = self.assign();
+            if attr.is_static {
+                self.global_initializer(obj);
+                continue;
+            }
+            cur.extend(self.lvar_initializer(obj));
+        }
+
+        Node::new(NodeKind::Block(cur))
+    }
+
+    // ブロック内の extern 宣言はグローバル変数を参照する。同じ名前のグローバル変数があればその宣言とまとめ、
+    // なければ globals には加えずに、このブロックの中でだけ見える名前として記録する
+    fn block_extern(&mut self, name: &'src str, ty: CTypeRef<'src>, attr: &VarAttr) {
+        let is_function = matches!(ty.borrow().kind, CTypeKind::Function { .. });
+        if !is_function && self.find_function(name).is_some() {
+            self.error_at(&format!("'{name}' redeclared as different kind of symbol"));
+        }
+        let obj = match self.find_global_var(name) {
+            Some(idx) => self.redeclare_global_var(idx, ty, attr),
+            None => {
+                let obj = Object::Object {
+                    id: self.var_count,
+                    name,
+                    ctype: ty,
+                    is_local: false,
+                    is_static: false,
+                    is_definition: false,
+                    init_data: None,
+                };
+                self.var_count += 1;
+                obj
+            }
+        };
+        self.scope.push(ScopedIdent {
+            name,
+            kind: ScopedIdentKind::Extern(obj),
+        });
+    }
+
+    // ローカル変数の初期化子を、変数を 0 で埋めてから要素ごとに代入する文の並びにする
+    fn lvar_initializer(&mut self, obj: Object<'src>) -> Vec<Node<'src>> {
+        let Object::Object { id, ctype, .. } = &obj else {
+            unreachable!()
+        };
+        let id = *id;
+        let init = self.initializer(Rc::clone(ctype));
+
+        // 長さが省略された配列は初期化子から長さが決まる
+        if let Some(Object::Object { ctype, .. }) = self
+            .locals
+            .iter_mut()
+            .find(|local| matches!(local, Object::Object { id: i, .. } if *i == id))
+        {
+            *ctype = Rc::clone(&init.ty);
+        }
+        let mut obj = obj;
+        if let Object::Object { ctype, .. } = &mut obj {
+            *ctype = Rc::clone(&init.ty);
+        }
+        let var = Node::new(NodeKind::Var(Box::new(obj)));
+
+        let mut nodes = vec![];
+        if init.expr.is_none() {
+            nodes.push(Node::new(NodeKind::ExprStmt(Box::new(Node::new(
+                NodeKind::MemZero(Box::new(var.clone())),
+            )))));
+        }
+        Self::create_lvar_init(&init, var, &mut nodes);
+        nodes
+    }
+
+    fn create_lvar_init(init: &Initializer<'src>, dest: Node<'src>, nodes: &mut Vec<Node<'src>>) {
+        if let Some(expr) = &init.expr {
             let node = Node::new(NodeKind::BinOp {
                 op: BinOp::Assign,
-                lhs: Box::new(lhs),
-                rhs: Box::new(rhs),
+                lhs: Box::new(dest),
+                rhs: Box::new(expr.clone()),
             });
-            cur.push(Node::new(NodeKind::ExprStmt(Box::new(node))));
+            nodes.push(Node::new(NodeKind::ExprStmt(Box::new(node))));
+            return;
         }
 
-        Node::new(NodeKind::Block(cur))
+        match &init.ty.borrow().kind {
+            CTypeKind::Array { .. } => {
+                for (i, child) in init.children.iter().enumerate() {
+                    let dest = Node::new(NodeKind::Deref(Box::new(Node::new(NodeKind::BinOp {
+                        op: BinOp::Add,
+                        lhs: Box::new(dest.clone()),
+                        rhs: Box::new(Node::new(NodeKind::Num(i as i64))),
+                    }))));
+                    Self::create_lvar_init(child, dest, nodes);
+                }
+            }
+            CTypeKind::Struct { members, .. } => {
+                for (member, child) in members.iter().zip(&init.children) {
+                    let dest = Node::new(NodeKind::Member {
+                        member: member.clone(),
+                        node: Box::new(dest.clone()),
+                    });
+                    Self::create_lvar_init(child, dest, nodes);
+                }
+            }
+            CTypeKind::Union { members, .. } => {
+                if let Some(i) = init.union_member {
+                    let dest = Node::new(NodeKind::Member {
+                        member: members[i].clone(),
+                        node: Box::new(dest),
+                    });
+                    Self::create_lvar_init(&init.children[i], dest, nodes);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn initializer(&mut self, ty: CTypeRef<'src>) -> Initializer<'src> {
+        let mut init = Initializer::new(ty, true);
+        self.initializer2(&mut init);
+        init
+    }
+
+    fn initializer2(&mut self, init: &mut Initializer<'src>) {
+        let ty = Rc::clone(&init.ty);
+        let ty = ty.borrow();
+        match &ty.kind {
+            CTypeKind::Array { base, .. } if base.borrow().size == 1 => {
+                if let TokenKind::String(s) = &self.tokens[self.cursor].kind {
+                    let s = s.clone();
+                    self.cursor += 1;
+                    self.string_initializer(init, Rc::clone(base), &s);
+                    return;
+                }
+                self.array_initializer(init, Rc::clone(base));
+            }
+            CTypeKind::Array { base, .. } => self.array_initializer(init, Rc::clone(base)),
+            CTypeKind::Struct { .. } | CTypeKind::Union { .. } => {
+                let is_union = matches!(ty.kind, CTypeKind::Union { .. });
+                if self.consume("{") {
+                    if is_union {
+                        self.union_initializer(init, true);
+                    } else {
+                        self.struct_initializer1(init);
+                    }
+                    return;
+                }
+
+                // 同じ型の値で初期化するか、括弧を省略してメンバを並べる。
+                // 読んだ式が構造体の値でなければ、最初のメンバの初期化子として使う
+                let is_string = matches!(self.tokens[self.cursor].kind, TokenKind::String(_));
+                if self.pending_init.is_none() && !is_string {
+                    let expr = self.assign();
+                    let expr_ty = TypedNode::from(expr.clone()).ctype.unwrap();
+                    if expr_ty.borrow().is_struct_or_union() {
+                        init.expr = Some(expr);
+                        return;
+                    }
+                    self.pending_init = Some(expr);
+                }
+                if is_union {
+                    self.union_initializer(init, false);
+                } else {
+                    self.struct_initializer2(init, 0);
+                }
+            }
+            _ => {
+                if self.consume("{") {
+                    self.initializer2(init);
+                    if !self.consume_end() {
+                        self.expect("}");
+                    }
+                    return;
+                }
+                init.expr = match self.pending_init.take() {
+                    Some(expr) => Some(expr),
+                    None => Some(self.assign()),
+                };
+            }
+        }
+    }
+
+    fn string_initializer(&mut self, init: &mut Initializer<'src>, base: CTypeRef<'src>, s: &str) {
+        if init.is_flexible {
+            *init = Initializer::new(array_of(base, s.len() + 1), false);
+        }
+
+        for (child, byte) in init.children.iter_mut().zip(s.bytes()) {
+            child.expr = Some(Node::new(NodeKind::Num(i64::from(byte))));
+        }
+    }
+
+    fn array_initializer(&mut self, init: &mut Initializer<'src>, base: CTypeRef<'src>) {
+        let has_brace = self.consume("{");
+
+        if has_brace {
+            self.array_initializer1(init);
+        } else {
+            self.array_initializer2(init, 0);
+        }
+
+        // 長さが省略された配列は、読んだ要素の数を長さにする
+        if init.is_flexible {
+            init.ty = array_of(base, init.children.len());
+            init.is_flexible = false;
+        }
+    }
+
+    // 括弧で囲まれた配列の初期化子。[i] = で初期化する要素を指定できる
+    fn array_initializer1(&mut self, init: &mut Initializer<'src>) {
+        let mut i = 0;
+        let mut is_first = true;
+        while !self.consume_end() {
+            if !is_first {
+                self.expect(",");
+            }
+            is_first = false;
+
+            if self.is_equal("[") {
+                i = self.array_designator(&init.ty);
+                init.extend_flexible(i + 1);
+                self.designation(&mut init.children[i]);
+                i += 1;
+                continue;
+            }
+
+            init.extend_flexible(i + 1);
+            if i < init.children.len() {
+                self.initializer2(&mut init.children[i]);
+            } else {
+                self.skip_excess_element();
+            }
+            i += 1;
+        }
+    }
+
+    // 括弧が省略された配列の初期化子。begin 番目から配列の長さ分まで要素を読む
+    fn array_initializer2(&mut self, init: &mut Initializer<'src>, begin: usize) {
+        let mut i = begin;
+        while init.is_flexible || i < init.children.len() {
+            if self.pending_init.is_none() && self.is_end() {
+                return;
+            }
+
+            let cursor = self.cursor;
+            if i > 0 {
+                self.expect(",");
+            }
+            // 指示子は括弧の中の初期化子に対するものなので、ここで読むのをやめる
+            if self.is_designator() {
+                self.cursor = cursor;
+                return;
+            }
+            init.extend_flexible(i + 1);
+            self.initializer2(&mut init.children[i]);
+            i += 1;
+        }
+    }
+
+    // 括弧で囲まれた構造体の初期化子。.member = で初期化するメンバを指定できる
+    fn struct_initializer1(&mut self, init: &mut Initializer<'src>) {
+        let mut i = 0;
+        let mut is_first = true;
+        while !self.consume_end() {
+            if !is_first {
+                self.expect(",");
+            }
+            is_first = false;
+
+            if self.is_equal(".") {
+                i = self.struct_designator(&init.ty);
+                self.designation(&mut init.children[i]);
+                i += 1;
+                continue;
+            }
+
+            if i < init.children.len() {
+                self.initializer2(&mut init.children[i]);
+            } else {
+                self.skip_excess_element();
+            }
+            i += 1;
+        }
+    }
+
+    // 括弧が省略された構造体の初期化子。begin 番目のメンバから読む
+    fn struct_initializer2(&mut self, init: &mut Initializer<'src>, begin: usize) {
+        for i in begin..init.children.len() {
+            if self.pending_init.is_none() && self.is_end() {
+                return;
+            }
+
+            let cursor = self.cursor;
+            if i > 0 {
+                self.expect(",");
+            }
+            if self.is_designator() {
+                self.cursor = cursor;
+                return;
+            }
+            self.initializer2(&mut init.children[i]);
+        }
+    }
+
+    // union は最初のメンバか、.member = で指定したメンバだけを初期化する
+    fn union_initializer(&mut self, init: &mut Initializer<'src>, has_brace: bool) {
+        if has_brace && self.is_equal(".") {
+            let i = self.struct_designator(&init.ty);
+            init.union_member = Some(i);
+            self.designation(&mut init.children[i]);
+        } else if !(init.children.is_empty() || has_brace && self.is_end()) {
+            init.union_member = Some(0);
+            self.initializer2(&mut init.children[0]);
+        }
+
+        if has_brace {
+            while !self.consume_end() {
+                self.expect(",");
+                self.skip_excess_element();
+            }
+        }
+    }
+
+    // 指示子の後ろの初期化子を読む。指示子は .a[1].b のように続けて書ける
+    fn designation(&mut self, init: &mut Initializer<'src>) {
+        if self.is_equal("[") {
+            if !matches!(init.ty.borrow().kind, CTypeKind::Array { .. }) {
+                self.error_at("array index in non-array initializer");
+            }
+            let i = self.array_designator(&init.ty);
+            self.designation(&mut init.children[i]);
+            self.array_initializer2(init, i + 1);
+            return;
+        }
+
+        if self.is_equal(".") {
+            let is_union = match init.ty.borrow().kind {
+                CTypeKind::Struct { .. } => false,
+                CTypeKind::Union { .. } => true,
+                _ => self.error_at("field name not in struct or union initializer"),
+            };
+            let i = self.struct_designator(&init.ty);
+            if is_union {
+                init.union_member = Some(i);
+                self.designation(&mut init.children[i]);
+                return;
+            }
+            init.expr = None;
+            self.designation(&mut init.children[i]);
+            self.struct_initializer2(init, i + 1);
+            return;
+        }
+
+        self.expect("=");
+        self.initializer2(init);
+    }
+
+    // [i] を読んで添字を返す
+    fn array_designator(&mut self, ty: &CTypeRef<'src>) -> usize {
+        self.expect("[");
+        let cursor = self.cursor;
+        let index = self.const_expr();
+        let is_out_of_bounds = match ty.borrow().kind {
+            CTypeKind::Array {
+                len, is_incomplete, ..
+            } => index < 0 || !is_incomplete && index as usize >= len,
+            _ => false,
+        };
+        if is_out_of_bounds {
+            self.cursor = cursor;
+            self.error_at("array designator index exceeds array bounds");
+        }
+        self.expect("]");
+        index as usize
+    }
+
+    // .member を読んでメンバの添字を返す
+    fn struct_designator(&mut self, ty: &CTypeRef<'src>) -> usize {
+        self.expect(".");
+        let token = self.tokens[self.cursor].clone();
+        let name = self.get_ident(token);
+
+        let index = match &ty.borrow().kind {
+            CTypeKind::Struct { members, .. } | CTypeKind::Union { members, .. } => {
+                members.iter().position(|member| member.name == name)
+            }
+            _ => None,
+        };
+        let Some(index) = index else {
+            self.error_at(&format!("no member named '{name}'"));
+        };
+        self.cursor += 1;
+        index
+    }
+
+    fn is_designator(&self) -> bool {
+        self.is_equal("[") || self.is_equal(".")
+    }
+
+    fn skip_excess_element(&mut self) {
+        if self.consume("{") {
+            self.skip_excess_element();
+            while !self.consume_end() {
+                self.expect(",");
+                self.skip_excess_element();
+            }
+            return;
+        }
+        self.assign();
+    }
+
+    // 初期化子の終わりの "}" か ",}" があれば読み飛ばす
+    fn consume_end(&mut self) -> bool {
+        if self.consume("}") {
+            return true;
+        }
+        if self.is_equal(",")
+            && self
+                .source_map
+                .span_to_str(&self.tokens[self.cursor + 1].span)
+                == "}"
+        {
+            self.cursor += 2;
+            return true;
+        }
+        false
+    }
+
+    fn is_end(&self) -> bool {
+        self.is_equal("}")
+            || self.is_equal(",")
+                && self
+                    .source_map
+                    .span_to_str(&self.tokens[self.cursor + 1].span)
+                    == "}"
     }
 
     fn compound_stmt(&mut self) -> Node<'src> {
         let mut nodes = vec![];
+        self.enter_scope();
         while !self.consume("}") {
-            nodes.push(if self.is_typename() {
-                self.declaration()
+            if self.is_typename() {
+                let mut attr = VarAttr::default();
+                let basety = self.declspec(Some(&mut attr));
+
+                if attr.is_typedef {
+                    self.parse_typedef(basety);
+                    continue;
+                }
+
+                nodes.push(self.declaration(basety, &attr));
             } else {
-                self.stmt()
-            })
+                nodes.push(self.stmt());
+            }
         }
+        self.leave_scope();
 
         Node::new(NodeKind::Block(nodes))
     }
@@ -622,6 +1980,199 @@ impl<'src> Parser<'src> {
         node
     }
 
+    fn const_expr(&mut self) -> i64 {
+        let start = self.cursor;
+        let node: TypedNode<'src> = self.conditional().into();
+        self.eval(&node).unwrap_or_else(|| {
+            self.cursor = start;
+            self.error_at("not a compile-time constant")
+        })
+    }
+
+    // 整数定数式を評価する。定数でなければ None を返す
+    fn eval(&self, node: &TypedNode<'src>) -> Option<i64> {
+        let mut label = None;
+        let value = self.eval_with_label(node, &mut label)?;
+        label.is_none().then_some(value)
+    }
+
+    // グローバル変数の初期化子では、他のグローバル変数のアドレス + 定数も定数として扱う。
+    // その場合は label にアドレスを取った変数のラベルを入れて、定数部分を返す
+    fn eval_with_label(&self, node: &TypedNode<'src>, label: &mut Option<String>) -> Option<i64> {
+        let ctype = node.ctype.as_ref()?.borrow();
+        match &node.kind {
+            TypedNodeKind::Num(value) => Some(*value),
+            TypedNodeKind::Cast(expr) => {
+                let expr_ty = expr.ctype.as_ref()?.borrow();
+                if expr_ty.is_flonum() {
+                    let value = self.eval_double(expr)?;
+                    if ctype.kind == CTypeKind::Bool {
+                        return Some(i64::from(value != 0.0));
+                    }
+                    let value = if ctype.is_unsigned && ctype.size == 8 {
+                        value as u64 as i64
+                    } else {
+                        value as i64
+                    };
+                    return Some(wrap_to_type(value, &ctype));
+                }
+
+                let value = self.eval_with_label(expr, label)?;
+                if ctype.kind == CTypeKind::Bool {
+                    return label.is_none().then_some(i64::from(value != 0));
+                }
+                if !ctype.is_integer() {
+                    return Some(value);
+                }
+                // アドレスは 64 bit より小さい型には変換できない
+                if label.is_some() && ctype.size < 8 {
+                    return None;
+                }
+
+                Some(wrap_to_type(value, &ctype))
+            }
+            TypedNodeKind::BinOp {
+                op: BinOp::Add,
+                lhs,
+                rhs,
+            } => {
+                let l = self.eval_with_label(lhs, label)?;
+                let mut rhs_label = None;
+                let r = self.eval_with_label(rhs, &mut rhs_label)?;
+                if rhs_label.is_some() {
+                    if label.is_some() {
+                        return None;
+                    }
+                    *label = rhs_label;
+                }
+                Some(wrap_to_type(l.wrapping_add(r), &ctype))
+            }
+            TypedNodeKind::BinOp {
+                op: BinOp::Sub,
+                lhs,
+                rhs,
+            } => {
+                let value = self
+                    .eval_with_label(lhs, label)?
+                    .wrapping_sub(self.eval(rhs)?);
+                Some(wrap_to_type(value, &ctype))
+            }
+            TypedNodeKind::BinOp { op, lhs, rhs } => {
+                let l = self.eval(lhs)?;
+                let is_unsigned = lhs.ctype.as_ref()?.borrow().is_unsigned;
+                if *op == BinOp::LogAnd {
+                    return Some(i64::from(l != 0 && self.eval(rhs)? != 0));
+                }
+                if *op == BinOp::LogOr {
+                    return Some(i64::from(l != 0 || self.eval(rhs)? != 0));
+                }
+
+                let r = self.eval(rhs)?;
+                let value = match op {
+                    BinOp::Mul => l.wrapping_mul(r),
+                    BinOp::Div | BinOp::Mod if r == 0 => return None,
+                    BinOp::Div if is_unsigned => ((l as u64) / (r as u64)) as i64,
+                    BinOp::Div => l.wrapping_div(r),
+                    BinOp::Mod if is_unsigned => ((l as u64) % (r as u64)) as i64,
+                    BinOp::Mod => l.wrapping_rem(r),
+                    BinOp::Eq => i64::from(l == r),
+                    BinOp::Ne => i64::from(l != r),
+                    BinOp::Lt if is_unsigned => i64::from((l as u64) < (r as u64)),
+                    BinOp::Lt => i64::from(l < r),
+                    BinOp::Le if is_unsigned => i64::from((l as u64) <= (r as u64)),
+                    BinOp::Le => i64::from(l <= r),
+                    BinOp::Comma => r,
+                    BinOp::BitAnd => l & r,
+                    BinOp::BitOr => l | r,
+                    BinOp::BitXor => l ^ r,
+                    BinOp::Shl => l.wrapping_shl(r as u32),
+                    BinOp::Shr if is_unsigned => ((l as u64) >> (r & 63)) as i64,
+                    BinOp::Shr => l.wrapping_shr(r as u32),
+                    _ => return None,
+                };
+                // 演算結果の型で表せない部分は捨てる
+                Some(wrap_to_type(value, &ctype))
+            }
+            TypedNodeKind::Cond { cond, then, els } => {
+                let cond = if cond.ctype.as_ref()?.borrow().is_flonum() {
+                    self.eval_double(cond)? != 0.0
+                } else {
+                    self.eval(cond)? != 0
+                };
+                self.eval_with_label(if cond { then } else { els }, label)
+            }
+            // 配列は先頭のアドレスになる
+            TypedNodeKind::Var(_) | TypedNodeKind::Member { .. }
+                if matches!(ctype.kind, CTypeKind::Array { .. }) =>
+            {
+                self.eval_rval(node, label)
+            }
+            TypedNodeKind::Addr(expr) => self.eval_rval(expr, label),
+            _ => None,
+        }
+    }
+
+    // 左辺値のアドレスを評価する
+    fn eval_rval(&self, node: &TypedNode<'src>, label: &mut Option<String>) -> Option<i64> {
+        match &node.kind {
+            TypedNodeKind::Var(obj) => {
+                *label = Some(obj.label()?);
+                Some(0)
+            }
+            TypedNodeKind::Deref(expr) => self.eval_with_label(expr, label),
+            TypedNodeKind::Member { node, member } => {
+                Some(self.eval_rval(node, label)? + member.offset as i64)
+            }
+            _ => None,
+        }
+    }
+
+    // 浮動小数点数の定数式を評価する
+    fn eval_double(&self, node: &TypedNode<'src>) -> Option<f64> {
+        let ctype = node.ctype.as_ref()?.borrow();
+        match &node.kind {
+            TypedNodeKind::FNum(value) => Some(*value),
+            TypedNodeKind::Cast(expr) => {
+                let expr_ty = expr.ctype.as_ref()?.borrow();
+                let value = if expr_ty.is_flonum() {
+                    self.eval_double(expr)?
+                } else if expr_ty.is_unsigned && expr_ty.size == 8 {
+                    self.eval(expr)? as u64 as f64
+                } else {
+                    self.eval(expr)? as f64
+                };
+
+                Some(if ctype.kind == CTypeKind::Float {
+                    f64::from(value as f32)
+                } else {
+                    value
+                })
+            }
+            TypedNodeKind::BinOp { op, lhs, rhs } => {
+                let l = self.eval_double(lhs)?;
+                let r = self.eval_double(rhs)?;
+                Some(match op {
+                    BinOp::Add => l + r,
+                    BinOp::Sub => l - r,
+                    BinOp::Mul => l * r,
+                    BinOp::Div => l / r,
+                    BinOp::Comma => r,
+                    _ => return None,
+                })
+            }
+            TypedNodeKind::Cond { cond, then, els } => {
+                let cond = if cond.ctype.as_ref()?.borrow().is_flonum() {
+                    self.eval_double(cond)? != 0.0
+                } else {
+                    self.eval(cond)? != 0
+                };
+                self.eval_double(if cond { then } else { els })
+            }
+            _ => None,
+        }
+    }
+
+    #[allow(clippy::wrong_self_convention)]
     fn to_assign(&mut self, binary: Node<'src>) -> Node<'src> {
         if let Node {
             kind: NodeKind::BinOp { op, lhs, rhs },
@@ -665,51 +2216,105 @@ impl<'src> Parser<'src> {
             });
         }
 
-        self.error_at("not a binary");
-    }
+        self.error_at("not a binary");
+    }
+
+    fn assign(&mut self) -> Node<'src> {
+        let mut node = self.conditional();
+
+        if self.consume("=") {
+            node = Node::new(NodeKind::BinOp {
+                op: BinOp::Assign,
+                lhs: Box::new(node),
+                rhs: Box::new(self.assign()),
+            })
+        }
+
+        if self.consume("+=") {
+            let rhs = Box::new(self.assign());
+            return self.to_assign(Node::new(NodeKind::BinOp {
+                op: BinOp::Add,
+                lhs: Box::new(node),
+                rhs,
+            }));
+        }
+
+        if self.consume("-=") {
+            let rhs = Box::new(self.assign());
+            return self.to_assign(Node::new(NodeKind::BinOp {
+                op: BinOp::Sub,
+                lhs: Box::new(node),
+                rhs,
+            }));
+        }
+
+        if self.consume("*=") {
+            let rhs = Box::new(self.assign());
+            return self.to_assign(Node::new(NodeKind::BinOp {
+                op: BinOp::Mul,
+                lhs: Box::new(node),
+                rhs,
+            }));
+        }
+
+        if self.consume("/=") {
+            let rhs = Box::new(self.assign());
+            return self.to_assign(Node::new(NodeKind::BinOp {
+                op: BinOp::Div,
+                lhs: Box::new(node),
+                rhs,
+            }));
+        }
 
-    fn assign(&mut self) -> Node<'src> {
-        let mut node = self.logor();
+        if self.consume("%=") {
+            let rhs = Box::new(self.assign());
+            return self.to_assign(Node::new(NodeKind::BinOp {
+                op: BinOp::Mod,
+                lhs: Box::new(node),
+                rhs,
+            }));
+        }
 
-        if self.consume("=") {
-            node = Node::new(NodeKind::BinOp {
-                op: BinOp::Assign,
+        if self.consume("&=") {
+            let rhs = Box::new(self.assign());
+            return self.to_assign(Node::new(NodeKind::BinOp {
+                op: BinOp::BitAnd,
                 lhs: Box::new(node),
-                rhs: Box::new(self.assign()),
-            })
+                rhs,
+            }));
         }
 
-        if self.consume("+=") {
+        if self.consume("|=") {
             let rhs = Box::new(self.assign());
             return self.to_assign(Node::new(NodeKind::BinOp {
-                op: BinOp::Add,
+                op: BinOp::BitOr,
                 lhs: Box::new(node),
                 rhs,
             }));
         }
 
-        if self.consume("-=") {
+        if self.consume("^=") {
             let rhs = Box::new(self.assign());
             return self.to_assign(Node::new(NodeKind::BinOp {
-                op: BinOp::Sub,
+                op: BinOp::BitXor,
                 lhs: Box::new(node),
                 rhs,
             }));
         }
 
-        if self.consume("*=") {
+        if self.consume("<<=") {
             let rhs = Box::new(self.assign());
             return self.to_assign(Node::new(NodeKind::BinOp {
-                op: BinOp::Mul,
+                op: BinOp::Shl,
                 lhs: Box::new(node),
                 rhs,
             }));
         }
 
-        if self.consume("/=") {
+        if self.consume(">>=") {
             let rhs = Box::new(self.assign());
             return self.to_assign(Node::new(NodeKind::BinOp {
-                op: BinOp::Div,
+                op: BinOp::Shr,
                 lhs: Box::new(node),
                 rhs,
             }));
@@ -718,6 +2323,22 @@ impl<'src> Parser<'src> {
         node
     }
 
+    fn conditional(&mut self) -> Node<'src> {
+        let cond = self.logor();
+        if !self.consume("?") {
+            return cond;
+        }
+
+        let then = self.expr();
+        self.expect(":");
+        let els = self.conditional();
+        Node::new(NodeKind::Cond {
+            cond: Box::new(cond),
+            then: Box::new(then),
+            els: Box::new(els),
+        })
+    }
+
     fn logor(&mut self) -> Node<'src> {
         let mut node = self.logand();
 
@@ -726,7 +2347,7 @@ impl<'src> Parser<'src> {
                 node = Node::new(NodeKind::BinOp {
                     op: BinOp::LogOr,
                     lhs: Box::new(node),
-                    rhs: Box::new(self.equality()),
+                    rhs: Box::new(self.logand()),
                 })
             } else {
                 return node;
@@ -735,14 +2356,14 @@ impl<'src> Parser<'src> {
     }
 
     fn logand(&mut self) -> Node<'src> {
-        let mut node = self.equality();
+        let mut node = self.bitor();
 
         loop {
             if self.consume("&&") {
                 node = Node::new(NodeKind::BinOp {
                     op: BinOp::LogAnd,
                     lhs: Box::new(node),
-                    rhs: Box::new(self.equality()),
+                    rhs: Box::new(self.bitor()),
                 })
             } else {
                 return node;
@@ -750,6 +2371,54 @@ impl<'src> Parser<'src> {
         }
     }
 
+    fn bitor(&mut self) -> Node<'src> {
+        let mut node = self.bitxor();
+
+        loop {
+            if self.consume("|") {
+                node = Node::new(NodeKind::BinOp {
+                    op: BinOp::BitOr,
+                    lhs: Box::new(node),
+                    rhs: Box::new(self.bitxor()),
+                });
+            } else {
+                return node;
+            }
+        }
+    }
+
+    fn bitxor(&mut self) -> Node<'src> {
+        let mut node = self.bitand();
+
+        loop {
+            if self.consume("^") {
+                node = Node::new(NodeKind::BinOp {
+                    op: BinOp::BitXor,
+                    lhs: Box::new(node),
+                    rhs: Box::new(self.bitand()),
+                });
+            } else {
+                return node;
+            }
+        }
+    }
+
+    fn bitand(&mut self) -> Node<'src> {
+        let mut node = self.equality();
+
+        loop {
+            if self.consume("&") {
+                node = Node::new(NodeKind::BinOp {
+                    op: BinOp::BitAnd,
+                    lhs: Box::new(node),
+                    rhs: Box::new(self.equality()),
+                });
+            } else {
+                return node;
+            }
+        }
+    }
+
     fn equality(&mut self) -> Node<'src> {
         let mut node = self.relational();
 
@@ -773,31 +2442,31 @@ impl<'src> Parser<'src> {
     }
 
     fn relational(&mut self) -> Node<'src> {
-        let mut node = self.add();
+        let mut node = self.shift();
 
         loop {
             if self.consume("<") {
                 node = Node::new(NodeKind::BinOp {
                     op: BinOp::Lt,
                     lhs: Box::new(node),
-                    rhs: Box::new(self.add()),
+                    rhs: Box::new(self.shift()),
                 });
             } else if self.consume("<=") {
                 node = Node::new(NodeKind::BinOp {
                     op: BinOp::Le,
                     lhs: Box::new(node),
-                    rhs: Box::new(self.add()),
+                    rhs: Box::new(self.shift()),
                 });
             } else if self.consume(">") {
                 node = Node::new(NodeKind::BinOp {
                     op: BinOp::Lt,
-                    lhs: Box::new(self.add()),
+                    lhs: Box::new(self.shift()),
                     rhs: Box::new(node),
                 });
             } else if self.consume(">=") {
                 node = Node::new(NodeKind::BinOp {
                     op: BinOp::Le,
-                    lhs: Box::new(self.add()),
+                    lhs: Box::new(self.shift()),
                     rhs: Box::new(node),
                 });
             } else {
@@ -806,6 +2475,28 @@ impl<'src> Parser<'src> {
         }
     }
 
+    fn shift(&mut self) -> Node<'src> {
+        let mut node = self.add();
+
+        loop {
+            if self.consume("<<") {
+                node = Node::new(NodeKind::BinOp {
+                    op: BinOp::Shl,
+                    lhs: Box::new(node),
+                    rhs: Box::new(self.add()),
+                });
+            } else if self.consume(">>") {
+                node = Node::new(NodeKind::BinOp {
+                    op: BinOp::Shr,
+                    lhs: Box::new(node),
+                    rhs: Box::new(self.add()),
+                });
+            } else {
+                return node;
+            }
+        }
+    }
+
     fn add(&mut self) -> Node<'src> {
         let mut node = self.mul();
         loop {
@@ -828,25 +2519,25 @@ impl<'src> Parser<'src> {
     }
 
     fn mul(&mut self) -> Node<'src> {
-        let mut node = self.unary();
+        let mut node = self.cast();
         loop {
             if self.consume("*") {
                 node = Node::new(NodeKind::BinOp {
                     op: BinOp::Mul,
                     lhs: Box::new(node),
-                    rhs: Box::new(self.unary()),
+                    rhs: Box::new(self.cast()),
                 });
             } else if self.consume("/") {
                 node = Node::new(NodeKind::BinOp {
                     op: BinOp::Div,
                     lhs: Box::new(node),
-                    rhs: Box::new(self.unary()),
+                    rhs: Box::new(self.cast()),
                 });
             } else if self.consume("%") {
                 node = Node::new(NodeKind::BinOp {
                     op: BinOp::Mod,
                     lhs: Box::new(node),
-                    rhs: Box::new(self.unary()),
+                    rhs: Box::new(self.cast()),
                 })
             } else {
                 return node;
@@ -854,32 +2545,68 @@ impl<'src> Parser<'src> {
         }
     }
 
+    fn is_cast(&mut self) -> bool {
+        if !self.is_equal("(") {
+            return false;
+        }
+
+        let cursor = self.cursor;
+        self.cursor += 1;
+        let result = self.is_typename();
+        self.cursor = cursor;
+        result
+    }
+
+    fn cast(&mut self) -> Node<'src> {
+        if self.is_cast() {
+            self.expect("(");
+            let ctype = self.typename();
+            self.expect(")");
+
+            return Node::new(NodeKind::Cast {
+                node: Box::new(self.cast()),
+                ctype,
+            });
+        }
+
+        self.unary()
+    }
+
     fn unary(&mut self) -> Node<'src> {
         if self.consume("+") {
-            return self.unary();
+            return self.cast();
         }
 
         if self.consume("-") {
             return Node::new(NodeKind::BinOp {
                 op: BinOp::Sub,
                 lhs: Box::new(Node::new(NodeKind::Num(0))),
-                rhs: Box::new(self.unary()),
+                rhs: Box::new(self.cast()),
             });
         }
 
         if self.consume("&") {
-            return Node::new(NodeKind::Addr(Box::new(self.unary())));
+            return Node::new(NodeKind::Addr(Box::new(self.cast())));
         }
 
         if self.consume("*") {
-            return Node::new(NodeKind::Deref(Box::new(self.unary())));
+            return Node::new(NodeKind::Deref(Box::new(self.cast())));
         }
 
         if self.consume("!") {
             return Node::new(NodeKind::BinOp {
                 op: BinOp::Eq,
                 lhs: Box::new(Node::new(NodeKind::Num(0))),
-                rhs: Box::new(self.unary()),
+                rhs: Box::new(self.cast()),
+            });
+        }
+
+        // ~x は全てのビットが立った値との排他的論理和
+        if self.consume("~") {
+            return Node::new(NodeKind::BinOp {
+                op: BinOp::BitXor,
+                lhs: Box::new(self.cast()),
+                rhs: Box::new(Node::new(NodeKind::Num(-1))),
             });
         }
 
@@ -890,7 +2617,7 @@ impl<'src> Parser<'src> {
         let mut members = vec![];
 
         while !self.consume("}") {
-            let basety = self.declspec();
+            let basety = self.declspec(None);
             let mut i = 0;
 
             while !self.consume(";") {
@@ -898,10 +2625,8 @@ impl<'src> Parser<'src> {
                     self.expect(",");
                 }
 
-                let ty = self.declarator(Rc::clone(&basety));
-                let name = self
-                    .source_map
-                    .span_to_str(&ty.borrow().name.clone().unwrap().span);
+                let (ty, name) = self.declarator(Rc::clone(&basety));
+                let name = self.source_map.span_to_str(&name.span);
                 members.push(Member {
                     ty,
                     name,
@@ -915,7 +2640,7 @@ impl<'src> Parser<'src> {
         members
     }
 
-    fn struct_union_decl(&mut self) -> CTypeRef<'src> {
+    fn struct_union_decl(&mut self, is_union: bool) -> CTypeRef<'src> {
         let mut tag = None;
         if self.tokens[self.cursor].kind == TokenKind::Ident {
             let token = &self.tokens[self.cursor];
@@ -932,15 +2657,7 @@ impl<'src> Parser<'src> {
             }
 
             // タグが設定されている && タグが存在しない && structのメンバの定義がない場合は incomplete な定義を追加
-            let ty = CType::new(
-                CTypeKind::Struct {
-                    members: vec![],
-                    is_incomplete: true,
-                },
-                None,
-                0,
-                0,
-            );
+            let ty = CType::new(aggregate_kind(vec![], true, is_union), None, 0, 0);
 
             self.push_tag(tag_name, ty.clone());
             return ty;
@@ -950,42 +2667,24 @@ impl<'src> Parser<'src> {
 
         let new_members = self.struct_members();
         if let Some(tag_name) = tag {
-            if let Some(tag) = self.find_tag(tag_name) {
+            // 同じスコープで宣言済みの不完全型があればそれを完成させる
+            if let Some(tag) = self.find_tag_in_current_scope(tag_name) {
                 let mut ty_mut = tag.ty.borrow_mut();
-                ty_mut.kind = CTypeKind::Struct {
-                    members: new_members,
-                    is_incomplete: false,
-                };
+                ty_mut.kind = aggregate_kind(new_members, false, is_union);
 
                 return Rc::clone(&tag.ty);
             } else {
-                let ty = CType::new(
-                    CTypeKind::Struct {
-                        members: new_members,
-                        is_incomplete: false,
-                    },
-                    None,
-                    0,
-                    1,
-                );
+                let ty = CType::new(aggregate_kind(new_members, false, is_union), None, 0, 1);
                 self.push_tag(tag_name, Rc::clone(&ty));
                 return ty;
             }
         }
 
-        CType::new(
-            CTypeKind::Struct {
-                members: new_members,
-                is_incomplete: false,
-            },
-            None,
-            0,
-            1,
-        )
+        CType::new(aggregate_kind(new_members, false, is_union), None, 0, 1)
     }
 
     fn struct_decl(&mut self) -> CTypeRef<'src> {
-        let ty = self.struct_union_decl();
+        let ty = self.struct_union_decl(false);
 
         {
             let mut ty_mut = ty.borrow_mut();
@@ -1021,6 +2720,106 @@ impl<'src> Parser<'src> {
         ty
     }
 
+    fn enum_specifier(&mut self) -> CTypeRef<'src> {
+        let mut tag = None;
+        if self.tokens[self.cursor].kind == TokenKind::Ident {
+            let token = &self.tokens[self.cursor];
+            self.cursor += 1;
+            tag = Some(self.source_map.span_to_str(&token.span));
+        }
+
+        if let Some(tag_name) = tag
+            && !self.is_equal("{")
+        {
+            let Some(tag) = self.find_tag(tag_name) else {
+                self.cursor -= 1;
+                self.error_at("unknown enum type");
+            };
+            let ty = Rc::clone(&tag.ty);
+            if ty.borrow().kind != CTypeKind::Enum {
+                self.cursor -= 1;
+                self.error_at("not an enum tag");
+            }
+
+            return ty;
+        }
+
+        self.expect("{");
+
+        // 列挙定数は 0 から始まり、明示的に値が与えられなければ直前の値 + 1 になる
+        let ty = CType::enum_type();
+        let mut value = 0;
+        let mut is_first = true;
+        while !self.consume("}") {
+            if !is_first {
+                self.expect(",");
+                // 末尾のカンマを許す
+                if self.consume("}") {
+                    break;
+                }
+            }
+            is_first = false;
+
+            let name = self.get_ident(self.tokens[self.cursor].clone());
+            self.cursor += 1;
+
+            let start = self.cursor;
+            if self.consume("=") {
+                value = self.const_expr();
+            }
+            // 列挙定数の型は int
+            if i32::try_from(value).is_err() {
+                self.cursor = start - 1;
+                self.error_at("enumerator value is outside the range of int");
+            }
+
+            self.scope.push(ScopedIdent {
+                name,
+                kind: ScopedIdentKind::EnumConst(value),
+            });
+            value += 1;
+        }
+
+        if let Some(tag_name) = tag {
+            self.push_tag(tag_name, Rc::clone(&ty));
+        }
+
+        ty
+    }
+
+    fn union_decl(&mut self) -> CTypeRef<'src> {
+        let ty = self.struct_union_decl(true);
+
+        {
+            let mut ty_mut = ty.borrow_mut();
+            let CTypeKind::Union {
+                members,
+                is_incomplete,
+            } = &mut ty_mut.kind
+            else {
+                self.error_at("not a union");
+            };
+
+            if *is_incomplete {
+                return ty.to_owned();
+            }
+
+            // union のメンバは全て offset 0 に置かれ、サイズは最大のメンバに合わせる
+            let mut size = 0;
+            let mut align = 1;
+            for member in members {
+                member.offset = 0;
+                size = size.max(member.ty.borrow().size);
+                align = align.max(member.ty.borrow().align);
+            }
+
+            ty_mut.size = align_to(size, align);
+            ty_mut.align = align;
+        }
+
+        ty
+    }
+
     fn push_tag(&mut self, tag: &'src str, ty: CTypeRef<'src>) {
         self.tags.push(Tag { name: tag, ty });
     }
@@ -1029,11 +2828,16 @@ impl<'src> Parser<'src> {
         self.tags.iter_mut().rev().find(|t| t.name == tag)
     }
 
+    fn find_tag_in_current_scope(&mut self, tag: &str) -> Option<&mut Tag<'src>> {
+        let start = self.scope_marks.last().map_or(0, |&(_, tags_len)| tags_len);
+        self.tags[start..].iter_mut().rev().find(|t| t.name == tag)
+    }
+
     fn get_struct_member(&mut self, ty: CTypeRef<'src>, token: &Token) -> Member<'src> {
         let raw_token = self.source_map.span_to_str(&token.span);
 
         if let CType {
-            kind: CTypeKind::Struct { members, .. },
+            kind: CTypeKind::Struct { members, .. } | CTypeKind::Union { members, .. },
             ..
         } = &*ty.borrow()
         {
@@ -1056,8 +2860,14 @@ impl<'src> Parser<'src> {
             .ctype
             .map(|ty| Rc::clone(&ty))
             .unwrap();
-        if !matches!(lhs_type.borrow().kind, CTypeKind::Struct { .. }) {
-            self.error_at(&format!("not a struct: {:#?}", lhs_type.borrow().kind));
+        if !matches!(
+            lhs_type.borrow().kind,
+            CTypeKind::Struct { .. } | CTypeKind::Union { .. }
+        ) {
+            self.error_at(&format!(
+                "not a struct nor a union: {:#?}",
+                lhs_type.borrow().kind
+            ));
         }
 
         let member = self.get_struct_member(lhs_type, token);
@@ -1128,6 +2938,75 @@ impl<'src> Parser<'src> {
         }
     }
 
+    // va_start(ap, last) は ap に最初の可変長部分の引数のアドレスを設定する
+    fn va_start(&mut self) -> Node<'src> {
+        if !self.current_is_variadic {
+            self.error_at("va_start used in function with fixed args");
+        }
+
+        self.expect("(");
+        let ap = self.assign();
+        self.expect(",");
+        self.assign();
+        self.expect(")");
+        Node::new(NodeKind::VaStart(Box::new(ap)))
+    }
+
+    // va_arg(ap, ty) は (ap = (char *)ap + n, *(ty *)((char *)ap - n)) に展開する。
+    // 可変長部分の引数は 8 バイト単位で並んでいて、16 バイトを超える構造体はコピーへのアドレスが入っている
+    fn va_arg(&mut self) -> Node<'src> {
+        self.expect("(");
+        let ap = self.assign();
+        self.expect(",");
+        let ty = self.typename();
+        self.expect(")");
+
+        let is_by_ref = ty.borrow().is_struct_or_union() && ty.borrow().size > 16;
+        let size = if is_by_ref {
+            8
+        } else {
+            align_to(ty.borrow().size, 8)
+        };
+        let char_ap = || {
+            Box::new(Node::new(NodeKind::Cast {
+                node: Box::new(ap.clone()),
+                ctype: CType::pointer_to(CType::char()),
+            }))
+        };
+
+        let advance = Node::new(NodeKind::BinOp {
+            op: BinOp::Assign,
+            lhs: Box::new(ap.clone()),
+            rhs: Box::new(Node::new(NodeKind::BinOp {
+                op: BinOp::Add,
+                lhs: char_ap(),
+                rhs: Box::new(Node::new(NodeKind::Num(size as i64))),
+            })),
+        });
+        let arg_ptr_ty = if is_by_ref {
+            CType::pointer_to(CType::pointer_to(Rc::clone(&ty)))
+        } else {
+            CType::pointer_to(Rc::clone(&ty))
+        };
+        let mut arg = Node::new(NodeKind::Deref(Box::new(Node::new(NodeKind::Cast {
+            node: Box::new(Node::new(NodeKind::BinOp {
+                op: BinOp::Sub,
+                lhs: char_ap(),
+                rhs: Box::new(Node::new(NodeKind::Num(size as i64))),
+            })),
+            ctype: arg_ptr_ty,
+        }))));
+        if is_by_ref {
+            arg = Node::new(NodeKind::Deref(Box::new(arg)));
+        }
+
+        Node::new(NodeKind::BinOp {
+            op: BinOp::Comma,
+            lhs: Box::new(advance),
+            rhs: Box::new(arg),
+        })
+    }
+
     fn funcall(&mut self) -> Node<'src> {
         let name = self.source_map.span_to_str(&self.tokens[self.cursor].span);
         // ident と "(" を消費
@@ -1143,11 +3022,71 @@ impl<'src> Parser<'src> {
 
             cur.push(self.assign());
         }
-        if let Some(Object::Function { ret_type, .. }) = self.find_var(name) {
+        if let Some(Object::Function {
+            ret_type,
+            params,
+            is_variadic,
+            ..
+        }) = self.find_var(name)
+        {
+            // プロトタイプがある場合は引数を仮引数の型に変換する。
+            // 対応する仮引数がない引数は float を double に昇格させる
+            let args = cur
+                .into_iter()
+                .enumerate()
+                .map(|(i, arg)| match params.get(i) {
+                    Some(Object::Object { ctype, .. }) if ctype.borrow().is_numeric() => {
+                        Node::new(NodeKind::Cast {
+                            node: Box::new(arg),
+                            ctype: Rc::clone(ctype),
+                        })
+                    }
+                    None if TypedNode::from(arg.clone()).ctype.unwrap().borrow().kind
+                        == CTypeKind::Float =>
+                    {
+                        Node::new(NodeKind::Cast {
+                            node: Box::new(arg),
+                            ctype: CType::double(),
+                        })
+                    }
+                    _ => arg,
+                })
+                .collect::<Vec<_>>();
+
+            // 16 バイトを超える構造体は呼び出し側で作ったコピーへのアドレスを渡すので、
+            // 一時変数にコピーしておく
+            let args = args
+                .into_iter()
+                .map(|arg| {
+                    let ty = TypedNode::from(arg.clone()).ctype.unwrap();
+                    if !ty.borrow().is_struct_or_union() || ty.borrow().size <= 16 {
+                        return arg;
+                    }
+
+                    let tmp = Box::new(self.new_var("", ty, true));
+                    Node::new(NodeKind::BinOp {
+                        op: BinOp::Comma,
+                        lhs: Box::new(Node::new(NodeKind::BinOp {
+                            op: BinOp::Assign,
+                            lhs: Box::new(Node::new(NodeKind::Var(tmp.clone()))),
+                            rhs: Box::new(arg),
+                        })),
+                        rhs: Box::new(Node::new(NodeKind::Var(tmp))),
+                    })
+                })
+                .collect();
+
+            let ret_buf = ret_type
+                .borrow()
+                .is_struct_or_union()
+                .then(|| Box::new(self.new_var("", Rc::clone(&ret_type), true)));
+
             return Node::new(NodeKind::FuncCall {
                 name,
-                args: cur,
+                args,
                 ret_ty: ret_type,
+                variadic_from: is_variadic.then_some(params.len()),
+                ret_buf,
             });
         }
 
@@ -1180,15 +3119,52 @@ impl<'src> Parser<'src> {
 
             // log(&format!("TYPE: {ty:#?}"));
 
-            return Node::new(NodeKind::Num(ty.borrow().size as i32));
+            return Node::new(NodeKind::Cast {
+                node: Box::new(Node::new(NodeKind::Num(ty.borrow().size as i64))),
+                ctype: CType::ulong(),
+            });
         }
 
         if self.consume("sizeof") {
             let node = self.unary();
             let typed_node: TypedNode<'_> = node.into();
-            return Node::new(NodeKind::Num(
-                typed_node.ctype.unwrap().borrow().size as i32,
-            ));
+            return Node::new(NodeKind::Cast {
+                node: Box::new(Node::new(NodeKind::Num(
+                    typed_node.ctype.unwrap().borrow().size as i64,
+                ))),
+                ctype: CType::ulong(),
+            });
+        }
+
+        if self.consume("__builtin_va_start") {
+            return self.va_start();
+        }
+
+        if self.consume("__builtin_va_arg") {
+            return self.va_arg();
+        }
+
+        if self.consume("__builtin_va_end") {
+            self.expect("(");
+            let ap = self.assign();
+            self.expect(")");
+            return Node::new(NodeKind::Cast {
+                node: Box::new(ap),
+                ctype: CType::void(),
+            });
+        }
+
+        if self.consume("__builtin_va_copy") {
+            self.expect("(");
+            let dest = self.assign();
+            self.expect(",");
+            let src = self.assign();
+            self.expect(")");
+            return Node::new(NodeKind::BinOp {
+                op: BinOp::Assign,
+                lhs: Box::new(dest),
+                rhs: Box::new(src),
+            });
         }
 
         let token = &self.tokens[self.cursor];
@@ -1202,8 +3178,15 @@ impl<'src> Parser<'src> {
                 return self.funcall();
             }
 
-            // Variable
             let raw_str = self.source_map.span_to_str(&token.span);
+
+            // 列挙定数
+            if let Some(value) = self.find_enum_const(raw_str) {
+                self.cursor += 1;
+                return Node::new(NodeKind::Num(value));
+            }
+
+            // Variable
             let Some(var) = self.find_var(raw_str) else {
                 self.error_at(&format!(
                     "undefined variable: {:?} {:?} {:?}",
@@ -1223,12 +3206,41 @@ impl<'src> Parser<'src> {
 
         if let TokenKind::Char(c) = token.kind.clone() {
             self.cursor += 1;
-            let n = u32::from(c);
-            return Node::new(NodeKind::Num(i32::try_from(n).unwrap()));
+            return Node::new(NodeKind::Num(i64::from(u32::from(c))));
+        }
+
+        if let TokenKind::Float { value, is_double } = token.kind {
+            self.cursor += 1;
+            let node = Node::new(NodeKind::FNum(value));
+            if is_double {
+                return node;
+            }
+
+            return Node::new(NodeKind::Cast {
+                node: Box::new(node),
+                ctype: CType::float(),
+            });
         }
 
-        if matches!(token.kind, TokenKind::Num(..)) {
-            return Node::new(NodeKind::Num(self.expect_number()));
+        if let TokenKind::Num {
+            value,
+            is_unsigned,
+            is_long,
+        } = token.kind
+        {
+            self.cursor += 1;
+            let node = Node::new(NodeKind::Num(value));
+            let ctype = match (is_unsigned, is_long) {
+                (false, false) => return node,
+                (false, true) => CType::long(),
+                (true, false) => CType::uint(),
+                (true, true) => CType::ulong(),
+            };
+
+            return Node::new(NodeKind::Cast {
+                node: Box::new(node),
+                ctype,
+            });
         }
 
         self.error_at("expected an expression");
@@ -0,0 +1,1316 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Read, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    SourceMap,
+    lexer::{Lexer, Span, Token, TokenKind, convert_pp_number, convert_pp_tokens},
+};
+
+// ファイルを読んでトークン列にする。"-" なら標準入力から読む
+pub fn tokenize_file<'src>(source_map: &'src SourceMap<'src>, path: &str) -> Option<Vec<Token>> {
+    let mut source = String::new();
+    if path == "-" {
+        io::stdin().read_to_string(&mut source).ok()?;
+        return Some(tokenize_source(source_map, "<stdin>".to_string(), source));
+    }
+    source = fs::read_to_string(path).ok()?;
+    Some(tokenize_source(source_map, path.to_string(), source))
+}
+
+// 中身はエラー表示などでコンパイルが終わるまで参照されるので、解放せずに持っておく
+fn tokenize_source<'src>(
+    source_map: &'src SourceMap<'src>,
+    name: String,
+    source: String,
+) -> Vec<Token> {
+    let source: &'src str = Box::leak(source.into_boxed_str());
+    let base = source_map.add_file(name, source);
+    Lexer::new(source_map, source, base).lex()
+}
+
+// マクロ展開で作った字句を、ファイルとは別の領域に置いて字句解析する
+fn tokenize_scratch<'src>(source_map: &'src SourceMap<'src>, source: String) -> Vec<Token> {
+    let source: &'src str = Box::leak(source.into_boxed_str());
+    let base = source_map.add_scratch(source);
+    Lexer::new(source_map, source, base).lex()
+}
+
+#[derive(Clone)]
+struct Macro<'src> {
+    // 関数形式マクロの仮引数。オブジェクト形式マクロなら None。
+    // 可変長引数は最後に __VA_ARGS__ という名前で入れる
+    params: Option<Vec<&'src str>>,
+    is_variadic: bool,
+    body: Vec<Token>,
+    // __LINE__ などの展開する場所によって値が変わるマクロは、body の代わりにこれで展開結果を作る
+    handler: Option<MacroHandler>,
+}
+
+type MacroHandler = for<'src> fn(&mut Preprocessor<'src>, &Token) -> String;
+
+// 最初から定義されているマクロ。gakicc のターゲット (RV64GC, LP64D ABI の Linux) に合わせる
+const PREDEFINED_MACROS: [(&str, &str); 52] = [
+    ("__gakicc__", "1"),
+    ("__STDC__", "1"),
+    ("__STDC_VERSION__", "201112L"),
+    ("__STDC_HOSTED__", "1"),
+    ("__STDC_NO_ATOMICS__", "1"),
+    ("__STDC_NO_COMPLEX__", "1"),
+    ("__STDC_NO_THREADS__", "1"),
+    ("__STDC_NO_VLA__", "1"),
+    ("__riscv", "1"),
+    ("__riscv_xlen", "64"),
+    ("__riscv_flen", "64"),
+    ("__riscv_float_abi_double", "1"),
+    ("__riscv_mul", "1"),
+    ("__riscv_div", "1"),
+    ("__riscv_muldiv", "1"),
+    ("__riscv_fdiv", "1"),
+    ("__ELF__", "1"),
+    ("__linux", "1"),
+    ("__linux__", "1"),
+    ("__gnu_linux__", "1"),
+    ("__unix", "1"),
+    ("__unix__", "1"),
+    ("_LP64", "1"),
+    ("__LP64__", "1"),
+    ("__ORDER_LITTLE_ENDIAN__", "1234"),
+    ("__ORDER_BIG_ENDIAN__", "4321"),
+    ("__BYTE_ORDER__", "__ORDER_LITTLE_ENDIAN__"),
+    ("__CHAR_BIT__", "8"),
+    ("__SIZEOF_SHORT__", "2"),
+    ("__SIZEOF_INT__", "4"),
+    ("__SIZEOF_LONG__", "8"),
+    ("__SIZEOF_LONG_LONG__", "8"),
+    ("__SIZEOF_POINTER__", "8"),
+    ("__SIZEOF_FLOAT__", "4"),
+    ("__SIZEOF_DOUBLE__", "8"),
+    ("__SIZEOF_SIZE_T__", "8"),
+    ("__SIZEOF_PTRDIFF_T__", "8"),
+    ("__SIZEOF_WCHAR_T__", "4"),
+    ("__SCHAR_MAX__", "0x7f"),
+    ("__SHRT_MAX__", "0x7fff"),
+    ("__INT_MAX__", "0x7fffffff"),
+    ("__LONG_MAX__", "0x7fffffffffffffffL"),
+    ("__LONG_LONG_MAX__", "0x7fffffffffffffffLL"),
+    ("__SIZE_MAX__", "0xffffffffffffffffUL"),
+    ("__PTRDIFF_MAX__", "0x7fffffffffffffffL"),
+    ("__INTMAX_MAX__", "0x7fffffffffffffffL"),
+    ("__SIZE_TYPE__", "unsigned long"),
+    ("__PTRDIFF_TYPE__", "long"),
+    ("__WCHAR_TYPE__", "int"),
+    ("__INTMAX_TYPE__", "long"),
+    ("__UINTMAX_TYPE__", "unsigned long"),
+    ("__INTPTR_TYPE__", "long"),
+];
+
+// 展開する場所によって値が変わるマクロ
+const DYNAMIC_MACROS: [(&str, MacroHandler); 5] = [
+    ("__FILE__", |preprocessor, token| {
+        preprocessor.file_macro(token)
+    }),
+    ("__LINE__", |preprocessor, token| {
+        preprocessor.line_macro(token)
+    }),
+    ("__COUNTER__", |preprocessor, _| {
+        preprocessor.counter_macro()
+    }),
+    ("__DATE__", |preprocessor, _| preprocessor.date.clone()),
+    ("__TIME__", |preprocessor, _| preprocessor.time.clone()),
+];
+
+#[derive(PartialEq)]
+enum CondContext {
+    Then,
+    Elif,
+    Else,
+}
+
+// 処理中の #if, #ifdef, #ifndef
+struct CondIncl {
+    context: CondContext,
+    token: Token,
+    // これまでの分岐のどれかを取り込んだか
+    included: bool,
+}
+
+pub struct Preprocessor<'src> {
+    source_map: &'src SourceMap<'src>,
+    include_paths: Vec<String>,
+    macros: HashMap<&'src str, Macro<'src>>,
+    // これから読むトークン。末尾から順に取り出す
+    tokens: Vec<Token>,
+    cond_incls: Vec<CondIncl>,
+    // ファイル全体がインクルードガードで囲まれているファイルと、そのマクロの名前
+    include_guards: HashMap<String, &'src str>,
+    // __COUNTER__ の次の値
+    counter: u64,
+    // コンパイルを始めた日時の __DATE__ と __TIME__ の値
+    date: String,
+    time: String,
+}
+
+impl<'src> Preprocessor<'src> {
+    pub fn new(source_map: &'src SourceMap<'src>, include_paths: Vec<String>) -> Self {
+        let (date, time) = format_date_time(SystemTime::now());
+        let mut preprocessor = Self {
+            source_map,
+            include_paths,
+            macros: HashMap::new(),
+            tokens: vec![],
+            cond_incls: vec![],
+            include_guards: HashMap::new(),
+            counter: 0,
+            date,
+            time,
+        };
+        preprocessor.define_predefined_macros();
+        preprocessor
+    }
+
+    fn define_predefined_macros(&mut self) {
+        let source = PREDEFINED_MACROS
+            .iter()
+            .map(|(name, value)| format!("#define {name} {value}\n"))
+            .collect();
+        let tokens = tokenize_source(self.source_map, "<built-in>".to_string(), source);
+        self.tokens = tokens.into_iter().rev().collect();
+        // 各行の "#" を読んでからディレクティブとして処理する
+        while self.next_token().kind != TokenKind::Eof {
+            self.directive();
+        }
+
+        for (name, handler) in DYNAMIC_MACROS {
+            self.macros.insert(
+                name,
+                Macro {
+                    params: None,
+                    is_variadic: false,
+                    body: vec![],
+                    handler: Some(handler),
+                },
+            );
+        }
+    }
+
+    fn file_macro(&self, token: &Token) -> String {
+        let origin = token.origin.as_ref().unwrap_or(&token.span);
+        let (name, _, _) = self.source_map.location(origin);
+        format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+
+    fn line_macro(&self, token: &Token) -> String {
+        let origin = token.origin.as_ref().unwrap_or(&token.span);
+        let (_, line, _) = self.source_map.location(origin);
+        line.to_string()
+    }
+
+    fn counter_macro(&mut self) -> String {
+        self.counter += 1;
+        (self.counter - 1).to_string()
+    }
+
+    // -dM で使う。定義されているマクロを #define の形で名前順に書き出す
+    pub fn print_macros(&self, writer: &mut Box<dyn Write>) {
+        let mut names = self
+            .macros
+            .iter()
+            .filter(|(_, m)| m.handler.is_none())
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>();
+        names.sort();
+
+        for name in names {
+            let m = &self.macros[name];
+            write!(writer, "#define {name}").unwrap();
+            if let Some(params) = &m.params {
+                let mut params = params.clone();
+                if m.is_variadic {
+                    *params.last_mut().unwrap() = "...";
+                }
+                write!(writer, "({})", params.join(",")).unwrap();
+            }
+            for (i, token) in m.body.iter().enumerate() {
+                if i == 0 || token.has_space {
+                    write!(writer, " ").unwrap();
+                }
+                write!(writer, "{}", self.text(token)).unwrap();
+            }
+            writeln!(writer).unwrap();
+        }
+    }
+
+    pub fn preprocess(&mut self, tokens: Vec<Token>) -> Vec<Token> {
+        self.tokens = tokens.into_iter().rev().collect();
+
+        let mut output = vec![];
+        loop {
+            let token = self.next_token();
+            if token.kind == TokenKind::Eof {
+                output.push(token);
+                break;
+            }
+
+            if self.expand_macro(&token) {
+                continue;
+            }
+
+            // マクロの展開結果に出てきた # はディレクティブにならない
+            if !(token.at_bol && token.hideset.is_empty() && self.is_equal(&token, "#")) {
+                output.push(token);
+                continue;
+            }
+
+            self.directive();
+        }
+
+        if let Some(cond_incl) = self.cond_incls.last() {
+            self.error_at(&cond_incl.token, "unterminated conditional directive");
+        }
+
+        convert_pp_tokens(self.source_map, &mut output);
+        output
+    }
+
+    fn next_token(&mut self) -> Token {
+        self.tokens.pop().expect("token stream must end with Eof")
+    }
+
+    fn peek(&self) -> &Token {
+        self.tokens.last().expect("token stream must end with Eof")
+    }
+
+    fn text(&self, token: &Token) -> &'src str {
+        self.source_map.span_to_str(&token.span)
+    }
+
+    fn is_equal(&self, token: &Token, op: &str) -> bool {
+        token.kind != TokenKind::Eof && self.text(token) == op
+    }
+
+    fn error_at(&self, token: &Token, message: &str) -> ! {
+        self.source_map.error_at(token.error_span(), message)
+    }
+
+    fn is_line_end(&self) -> bool {
+        let token = self.peek();
+        token.at_bol || token.kind == TokenKind::Eof
+    }
+
+    // ディレクティブの行の残りのトークンを読む
+    fn read_line(&mut self) -> Vec<Token> {
+        let mut line = vec![];
+        while !self.is_line_end() {
+            line.push(self.next_token());
+        }
+        line
+    }
+
+    // ディレクティブの行の中の次のトークンを読む。行が終わっていたらエラー
+    fn next_in_line(&mut self, message: &str) -> Token {
+        if self.is_line_end() {
+            self.error_at(self.peek(), message);
+        }
+        self.next_token()
+    }
+
+    fn directive(&mut self) {
+        // "#" だけの行 (空指令) は何もしない
+        if self.is_line_end() {
+            return;
+        }
+
+        let token = self.next_token();
+        match self.text(&token) {
+            "include" => self.include(&token),
+            "define" => self.define(),
+            "undef" => {
+                let name = self.macro_name();
+                self.macros.remove(name);
+                self.read_line();
+            }
+            "if" => {
+                let included = self.eval_const_expr(&token);
+                self.push_cond_incl(token, included);
+            }
+            "ifdef" | "ifndef" => {
+                let name = self.macro_name();
+                let defined = self.macros.contains_key(name);
+                self.read_line();
+                let included = defined == (self.text(&token) == "ifdef");
+                self.push_cond_incl(token, included);
+            }
+            "elif" => {
+                let Some(cond_incl) = self.cond_incls.last_mut() else {
+                    self.error_at(&token, "#elif without #if");
+                };
+                if cond_incl.context == CondContext::Else {
+                    self.error_at(&token, "#elif after #else");
+                }
+                cond_incl.context = CondContext::Elif;
+
+                // 前の分岐を取り込んでいたら、条件は評価せずに読み飛ばす
+                if !cond_incl.included && self.eval_const_expr(&token) {
+                    self.cond_incls.last_mut().unwrap().included = true;
+                } else {
+                    self.skip_cond_incl();
+                }
+            }
+            "else" => {
+                let Some(cond_incl) = self.cond_incls.last_mut() else {
+                    self.error_at(&token, "#else without #if");
+                };
+                if cond_incl.context == CondContext::Else {
+                    self.error_at(&token, "#else after #else");
+                }
+                cond_incl.context = CondContext::Else;
+                let included = cond_incl.included;
+
+                self.read_line();
+                if included {
+                    self.skip_cond_incl();
+                }
+            }
+            "endif" => {
+                if self.cond_incls.pop().is_none() {
+                    self.error_at(&token, "#endif without #if");
+                }
+                self.read_line();
+            }
+            "error" | "warning" => {
+                let line = self.read_line();
+                let message = match (line.first(), line.last()) {
+                    (Some(first), Some(last)) => format!(
+                        "#{} {}",
+                        self.text(&token),
+                        self.source_map.span_to_str(&Span {
+                            lo: first.span.lo,
+                            hi: last.span.hi,
+                        })
+                    ),
+                    _ => format!("#{}", self.text(&token)),
+                };
+                if self.text(&token) == "error" {
+                    self.error_at(&token, &message);
+                }
+                self.source_map.warn_at(token.error_span(), &message);
+            }
+            "line" => {
+                let line = self.read_line();
+                let line = self.expand_all(line);
+                self.line_marker(&token, &line);
+            }
+            // 対応していない #pragma は無視する
+            "pragma" => {
+                self.read_line();
+            }
+            // GCC の -E が出力する "# 行番号 "ファイル名" フラグ..." の形の行マーカー
+            _ if token.kind == TokenKind::PPNum => {
+                let mut line = vec![token.clone()];
+                line.extend(self.read_line());
+                self.line_marker(&token, &line);
+            }
+            _ => self.error_at(&token, "invalid preprocessor directive"),
+        }
+    }
+
+    // 行番号と、あればファイル名を読んで、次の行からの位置の表示に使う
+    fn line_marker(&self, directive: &Token, line: &[Token]) {
+        let Some(number) = line.first() else {
+            self.error_at(directive, "expected a line number");
+        };
+        let TokenKind::Num { value, .. } = convert_pp_number(self.source_map, number) else {
+            self.error_at(number, "invalid line number");
+        };
+        if value < 0 || !self.text(number).bytes().all(|c| c.is_ascii_digit()) {
+            self.error_at(number, "invalid line number");
+        }
+
+        let name = match line.get(1).map(|token| &token.kind) {
+            Some(TokenKind::String(name)) => Some(name.clone()),
+            None => None,
+            Some(_) => self.error_at(&line[1], "invalid filename"),
+        };
+        self.source_map
+            .add_line_marker(&directive.span, value as usize, name);
+    }
+
+    fn push_cond_incl(&mut self, token: Token, included: bool) {
+        self.cond_incls.push(CondIncl {
+            context: CondContext::Then,
+            token,
+            included,
+        });
+        if !included {
+            self.skip_cond_incl();
+        }
+    }
+
+    // 条件が偽の分岐を、対応する #elif, #else, #endif の "#" の直前まで読み飛ばす。
+    // 間にある #if から #endif までは入れ子としてまとめて飛ばす
+    fn skip_cond_incl(&mut self) {
+        let mut depth = 0;
+        while self.peek().kind != TokenKind::Eof {
+            let token = self.next_token();
+            if !(token.at_bol && self.is_equal(&token, "#")) || self.is_line_end() {
+                continue;
+            }
+
+            match self.text(self.peek()) {
+                "if" | "ifdef" | "ifndef" => depth += 1,
+                "endif" if depth > 0 => depth -= 1,
+                "elif" | "else" | "endif" if depth == 0 => {
+                    self.tokens.push(token);
+                    return;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // #if, #elif の行の式を評価する
+    fn eval_const_expr(&mut self, directive: &Token) -> bool {
+        let line = self.read_line();
+        if line.is_empty() {
+            self.error_at(directive, "no expression");
+        }
+
+        // defined はマクロ展開の前に 0 か 1 に置き換える
+        let mut tokens = vec![];
+        let mut i = 0;
+        while i < line.len() {
+            let token = &line[i];
+            if !self.is_equal(token, "defined") {
+                tokens.push(token.clone());
+                i += 1;
+                continue;
+            }
+
+            let has_paren = line.get(i + 1).is_some_and(|next| self.is_equal(next, "("));
+            let name_index = i + 1 + usize::from(has_paren);
+            let Some(name) = line
+                .get(name_index)
+                .filter(|name| name.kind == TokenKind::Ident)
+            else {
+                self.error_at(token, "macro name must be an identifier");
+            };
+            if has_paren
+                && !line
+                    .get(name_index + 1)
+                    .is_some_and(|next| self.is_equal(next, ")"))
+            {
+                self.error_at(name, "expected ')'");
+            }
+
+            let defined = self.macros.contains_key(self.text(name));
+            tokens.push(Token {
+                kind: TokenKind::Num {
+                    value: i64::from(defined),
+                    is_unsigned: false,
+                    is_long: false,
+                },
+                ..token.clone()
+            });
+            i = name_index + 1 + usize::from(has_paren);
+        }
+
+        let tokens = self.expand_all(tokens);
+        let mut expr = CondExpr {
+            preprocessor: self,
+            tokens,
+            cursor: 0,
+            directive,
+            unevaluated: 0,
+        };
+        let value = expr.conditional();
+        if let Some(token) = expr.tokens.get(expr.cursor) {
+            self.error_at(token, "extra token in expression");
+        }
+        value.value != 0
+    }
+
+    fn macro_name(&mut self) -> &'src str {
+        let token = self.next_token();
+        if token.kind != TokenKind::Ident || token.at_bol {
+            self.error_at(&token, "macro name must be an identifier");
+        }
+        self.text(&token)
+    }
+
+    fn define(&mut self) {
+        let name = self.macro_name();
+
+        // 名前の直後に空白なしで ( が続くときだけ関数形式マクロになる
+        let mut params = None;
+        let mut is_variadic = false;
+        if !self.is_line_end() && self.is_equal(self.peek(), "(") && !self.peek().has_space {
+            self.next_token();
+            let (names, variadic) = self.read_macro_params();
+            params = Some(names);
+            is_variadic = variadic;
+        }
+
+        let body = self.read_line();
+        for (i, token) in body.iter().enumerate() {
+            if self.is_equal(token, "##") && (i == 0 || i == body.len() - 1) {
+                self.error_at(token, "'##' cannot appear at either end of macro expansion");
+            }
+            if let Some(params) = &params
+                && self.is_equal(token, "#")
+                && body
+                    .get(i + 1)
+                    .is_none_or(|next| !params.contains(&self.text(next)))
+            {
+                self.error_at(token, "'#' is not followed by a macro parameter");
+            }
+        }
+
+        self.macros.insert(
+            name,
+            Macro {
+                params,
+                is_variadic,
+                body,
+                handler: None,
+            },
+        );
+    }
+
+    // 関数形式マクロの仮引数を ")" まで読む
+    fn read_macro_params(&mut self) -> (Vec<&'src str>, bool) {
+        let mut params = vec![];
+        if !self.is_line_end() && self.is_equal(self.peek(), ")") {
+            self.next_token();
+            return (params, false);
+        }
+
+        loop {
+            let token = self.next_in_line("expected ')'");
+            if self.is_equal(&token, "...") {
+                params.push("__VA_ARGS__");
+                let token = self.next_in_line("expected ')'");
+                if !self.is_equal(&token, ")") {
+                    self.error_at(&token, "expected ')'");
+                }
+                return (params, true);
+            }
+
+            if token.kind != TokenKind::Ident {
+                self.error_at(&token, "expected a parameter name");
+            }
+            let name = self.text(&token);
+            if params.contains(&name) {
+                self.error_at(&token, &format!("duplicate macro parameter '{name}'"));
+            }
+            params.push(name);
+
+            let token = self.next_in_line("expected ')'");
+            if self.is_equal(&token, ")") {
+                return (params, false);
+            }
+            if !self.is_equal(&token, ",") {
+                self.error_at(&token, "expected ',' or ')'");
+            }
+        }
+    }
+
+    // token がマクロなら、展開した結果をこれから読むトークンの先頭に戻して true を返す。
+    // 展開結果のトークンには展開したマクロの名前を hideset として付けておき、
+    // 読み直したときに同じマクロが再帰的に展開されないようにする
+    fn expand_macro(&mut self, token: &Token) -> bool {
+        if token.kind != TokenKind::Ident {
+            return false;
+        }
+        let name = self.text(token);
+        if token.hideset.iter().any(|hidden| hidden == name) {
+            return false;
+        }
+        let Some(m) = self.macros.get(name).cloned() else {
+            return false;
+        };
+
+        if let Some(handler) = m.handler {
+            let text = handler(self, token);
+            let result = self
+                .new_token(token, text)
+                .expect("built-in macro must expand to a single token");
+            self.tokens.push(result);
+            return true;
+        }
+
+        let Some(params) = &m.params else {
+            let mut hideset = token.hideset.clone();
+            hideset.push(name.to_string());
+            // オブジェクト形式マクロも ## だけは処理する
+            let body = self.substitute(&[], &m.body, &[]);
+            self.push_expansion(token, body, &hideset);
+            return true;
+        };
+
+        // 関数形式マクロは、直後に ( がなければ普通の識別子として扱う
+        if !self
+            .tokens
+            .last()
+            .is_some_and(|next| self.is_equal(next, "("))
+        {
+            return false;
+        }
+        self.next_token();
+        let (args, rparen) = self.read_macro_args(token, params, m.is_variadic);
+
+        // 呼び出しの途中で別のマクロの展開が終わっていることがあるので、
+        // マクロ名と ")" の両方の hideset にあるものだけを引き継ぐ
+        let mut hideset: Vec<String> = token
+            .hideset
+            .iter()
+            .filter(|hidden| rparen.hideset.contains(hidden))
+            .cloned()
+            .collect();
+        hideset.push(name.to_string());
+
+        let body = self.substitute(params, &m.body, &args);
+        self.push_expansion(token, body, &hideset);
+        true
+    }
+
+    fn push_expansion(&mut self, token: &Token, mut body: Vec<Token>, hideset: &[String]) {
+        for body_token in &mut body {
+            for hidden in hideset {
+                if !body_token.hideset.contains(hidden) {
+                    body_token.hideset.push(hidden.clone());
+                }
+            }
+        }
+        let origin = token.origin.clone().unwrap_or_else(|| token.span.clone());
+        for body_token in &mut body {
+            body_token.origin = Some(origin.clone());
+        }
+        // 展開結果の先頭は、マクロを書いた位置の行頭や空白の情報を引き継ぐ
+        if let Some(first) = body.first_mut() {
+            first.at_bol = token.at_bol;
+            first.has_space = token.has_space;
+        }
+        self.tokens.extend(body.into_iter().rev());
+    }
+
+    // "(" の後から実引数を読む。仮引数ごとの実引数と、閉じ括弧のトークンを返す
+    fn read_macro_args(
+        &mut self,
+        macro_token: &Token,
+        params: &[&str],
+        is_variadic: bool,
+    ) -> (Vec<Vec<Token>>, Token) {
+        let named = params.len() - usize::from(is_variadic);
+
+        let mut args = vec![];
+        for i in 0..named {
+            if i > 0 {
+                let token = self.next_token();
+                if self.is_equal(&token, ")") {
+                    self.error_at(macro_token, "too few arguments");
+                }
+            }
+            args.push(self.read_macro_arg(macro_token, false));
+        }
+
+        if is_variadic {
+            // ... に対応する実引数がなければ空にする
+            if named > 0 && self.is_equal(self.peek(), ",") {
+                self.next_token();
+            }
+            args.push(self.read_macro_arg(macro_token, true));
+        }
+
+        let token = self.next_token();
+        if !self.is_equal(&token, ")") {
+            self.error_at(macro_token, "too many arguments");
+        }
+        (args, token)
+    }
+
+    // 実引数を 1 つ読む。括弧の中の "," では区切らない。
+    // read_rest なら __VA_ARGS__ として ")" までをまとめて読む
+    fn read_macro_arg(&mut self, macro_token: &Token, read_rest: bool) -> Vec<Token> {
+        let mut arg = vec![];
+        let mut depth = 0;
+        loop {
+            let Some(token) = self.tokens.last() else {
+                self.error_at(macro_token, "unterminated argument list invoking macro");
+            };
+            if token.kind == TokenKind::Eof {
+                self.error_at(macro_token, "unterminated argument list invoking macro");
+            }
+
+            if depth == 0 && (self.is_equal(token, ")") || !read_rest && self.is_equal(token, ","))
+            {
+                return arg;
+            }
+            if self.is_equal(token, "(") {
+                depth += 1;
+            } else if self.is_equal(token, ")") {
+                depth -= 1;
+            }
+            arg.push(self.next_token());
+        }
+    }
+
+    fn find_arg<'a>(
+        &self,
+        token: &Token,
+        params: &[&str],
+        args: &'a [Vec<Token>],
+    ) -> Option<&'a [Token]> {
+        if token.kind != TokenKind::Ident {
+            return None;
+        }
+        let name = self.text(token);
+        params
+            .iter()
+            .position(|param| *param == name)
+            .map(|i| args[i].as_slice())
+    }
+
+    // 置換リストの仮引数を実引数で置き換え、# と ## を処理する
+    fn substitute(&mut self, params: &[&str], body: &[Token], args: &[Vec<Token>]) -> Vec<Token> {
+        let mut output: Vec<Token> = vec![];
+        let mut i = 0;
+        while i < body.len() {
+            let token = &body[i];
+
+            // #x は実引数を文字列リテラルにする。オブジェクト形式マクロの # は普通のトークン
+            if self.is_equal(token, "#")
+                && let Some(arg) = body
+                    .get(i + 1)
+                    .and_then(|next| self.find_arg(next, params, args))
+            {
+                output.push(self.stringize(token, arg));
+                i += 2;
+                continue;
+            }
+
+            // x ## y は前後のトークンをつなげて 1 つのトークンにする。
+            // 空の実引数は何もないものとして扱う
+            if self.is_equal(token, "##") {
+                let rhs = &body[i + 1];
+                let (first, rest) = match self.find_arg(rhs, params, args) {
+                    Some([]) => {
+                        i += 2;
+                        continue;
+                    }
+                    Some([first, rest @ ..]) => (first, rest),
+                    None => (rhs, [].as_slice()),
+                };
+                match output.pop() {
+                    Some(lhs) => output.push(self.paste(&lhs, first)),
+                    None => output.push(first.clone()),
+                }
+                output.extend(rest.iter().cloned());
+                i += 2;
+                continue;
+            }
+
+            let Some(arg) = self.find_arg(token, params, args) else {
+                output.push(token.clone());
+                i += 1;
+                continue;
+            };
+
+            // ## の左側の実引数は展開しない。空なら右側だけが残る
+            if body
+                .get(i + 1)
+                .is_some_and(|next| self.is_equal(next, "##"))
+            {
+                if arg.is_empty() {
+                    let rhs = &body[i + 2];
+                    match self.find_arg(rhs, params, args) {
+                        Some(arg) => output.extend(arg.iter().cloned()),
+                        None => output.push(rhs.clone()),
+                    }
+                    i += 3;
+                } else {
+                    output.extend(arg.iter().cloned());
+                    i += 1;
+                }
+                continue;
+            }
+
+            // それ以外の実引数は、先にマクロを展開しきってから置き換える
+            let mut expanded = self.expand_all(arg.to_vec());
+            if let Some(first) = expanded.first_mut() {
+                first.at_bol = token.at_bol;
+                first.has_space = token.has_space;
+            }
+            output.extend(expanded);
+            i += 1;
+        }
+        output
+    }
+
+    // tokens の中のマクロをすべて展開する。ディレクティブは処理しない
+    fn expand_all(&mut self, tokens: Vec<Token>) -> Vec<Token> {
+        let rest = std::mem::replace(&mut self.tokens, tokens.into_iter().rev().collect());
+        let mut output = vec![];
+        while let Some(token) = self.tokens.pop() {
+            if !self.expand_macro(&token) {
+                output.push(token);
+            }
+        }
+        self.tokens = rest;
+        output
+    }
+
+    // 実引数のトークンを書かれたとおりにつなげて文字列リテラルにする
+    fn stringize(&self, hash: &Token, arg: &[Token]) -> Token {
+        let mut text = String::from("\"");
+        for (i, token) in arg.iter().enumerate() {
+            if i > 0 && (token.has_space || token.at_bol) {
+                text.push(' ');
+            }
+            let token_text = self.text(token);
+            match token.kind {
+                // 文字列や文字のリテラル (閉じていないものも含む) の中の \ と " はエスケープする
+                TokenKind::String(_) | TokenKind::Char(_) | TokenKind::Invalid(_) => {
+                    text.push_str(&token_text.replace('\\', "\\\\").replace('"', "\\\""));
+                }
+                _ => text.push_str(token_text),
+            }
+        }
+        text.push('"');
+
+        self.new_token(hash, text)
+            .expect("stringized text must be a single string literal")
+    }
+
+    fn paste(&self, lhs: &Token, rhs: &Token) -> Token {
+        let text = format!("{}{}", self.text(lhs), self.text(rhs));
+        match self.new_token(lhs, text.clone()) {
+            Some(token) => token,
+            None => self.error_at(lhs, &format!("pasting forms '{text}', an invalid token")),
+        }
+    }
+
+    // text を字句解析して 1 つのトークンを作る。行頭や空白の情報は template から引き継ぎ、
+    // エラーでは template の位置を示す。1 つのトークンにならなければ None
+    fn new_token(&self, template: &Token, text: String) -> Option<Token> {
+        let mut tokens = tokenize_scratch(self.source_map, text);
+        // 最後は Eof
+        if tokens.len() != 2 || matches!(tokens[0].kind, TokenKind::Invalid(_)) {
+            return None;
+        }
+        let mut token = tokens.swap_remove(0);
+        token.at_bol = template.at_bol;
+        token.has_space = template.has_space;
+        token.origin = Some(template.error_span().clone());
+        Some(token)
+    }
+
+    fn include(&mut self, directive: &Token) {
+        let mut line = self.read_line();
+
+        // #include FOO のようにマクロで書かれたファイル名は展開してから読む
+        if line
+            .first()
+            .is_some_and(|token| token.kind == TokenKind::Ident)
+        {
+            line = self.expand_all(line);
+        }
+
+        let (path, is_quoted) = match line.first() {
+            Some(Token {
+                kind: TokenKind::String(path),
+                ..
+            }) => (path.clone(), true),
+            Some(token) if self.is_equal(token, "<") => {
+                let Some(end) = line.iter().position(|token| self.is_equal(token, ">")) else {
+                    self.error_at(token, "expected '>'");
+                };
+                // <foo/bar.h> はトークンに分かれているので、元の文字列に戻す
+                let lo = line[1].span.lo;
+                let hi = line[end].span.lo;
+                (
+                    self.source_map.span_to_str(&Span { lo, hi }).to_string(),
+                    false,
+                )
+            }
+            _ => self.error_at(directive, "expected a filename"),
+        };
+
+        let Some(found) = self.search_include_paths(&path, is_quoted, directive) else {
+            self.error_at(directive, &format!("'{path}' file not found"));
+        };
+        // インクルードガードのマクロが定義済みなら、読んでも何も残らないので開かない
+        if self
+            .include_guards
+            .get(&found)
+            .is_some_and(|guard| self.macros.contains_key(guard))
+        {
+            return;
+        }
+
+        let Some(mut tokens) = tokenize_file(self.source_map, &found) else {
+            self.error_at(directive, &format!("cannot open '{found}'"));
+        };
+        if let Some(guard) = self.detect_include_guard(&tokens) {
+            self.include_guards.insert(found, guard);
+        }
+
+        // 取り込んだファイルの Eof は捨てて、続きをそのまま読む
+        tokens.pop();
+        self.tokens.extend(tokens.into_iter().rev());
+    }
+
+    // "..." ならインクルードしたファイルのディレクトリから、<...> ならインクルードパスから探す
+    fn search_include_paths(
+        &self,
+        path: &str,
+        is_quoted: bool,
+        directive: &Token,
+    ) -> Option<String> {
+        if path.starts_with('/') {
+            return Path::new(path).exists().then(|| path.to_string());
+        }
+
+        if is_quoted {
+            let current = self.source_map.file_name(&directive.span);
+            let dir = Path::new(&current).parent().unwrap_or(Path::new(""));
+            let candidate = dir.join(path);
+            if candidate.exists() {
+                return Some(candidate.to_string_lossy().into_owned());
+            }
+        }
+
+        self.include_paths
+            .iter()
+            .map(|dir| Path::new(dir).join(path))
+            .find(|candidate| candidate.exists())
+            .map(|candidate| candidate.to_string_lossy().into_owned())
+    }
+
+    // ファイル全体が "#ifndef X" "#define X" ... "#endif" で囲まれていれば X を返す
+    fn detect_include_guard(&self, tokens: &[Token]) -> Option<&'src str> {
+        let is_directive = |i: usize, name: &str| {
+            tokens[i].at_bol
+                && self.is_equal(&tokens[i], "#")
+                && tokens
+                    .get(i + 1)
+                    .is_some_and(|next| !next.at_bol && self.is_equal(next, name))
+        };
+
+        if tokens.len() < 6 || !is_directive(0, "ifndef") || tokens[2].kind != TokenKind::Ident {
+            return None;
+        }
+        let guard = self.text(&tokens[2]);
+        if !is_directive(3, "define") || !self.is_equal(&tokens[5], guard) {
+            return None;
+        }
+
+        // 最初の #ifndef に対応する #endif がファイルの最後にあるか
+        let mut depth = 0;
+        for i in 6..tokens.len() {
+            if is_directive(i, "if") || is_directive(i, "ifdef") || is_directive(i, "ifndef") {
+                depth += 1;
+            } else if depth == 0 && (is_directive(i, "elif") || is_directive(i, "else")) {
+                return None;
+            } else if is_directive(i, "endif") {
+                if depth == 0 {
+                    // 最後は Eof
+                    return (i + 3 == tokens.len()).then_some(guard);
+                }
+                depth -= 1;
+            }
+        }
+        None
+    }
+}
+
+// __DATE__ ("Oct 17 2026" の形) と __TIME__ ("12:34:56" の形) の値。タイムゾーンは扱わず UTC にする
+fn format_date_time(now: SystemTime) -> (String, String) {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let secs = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()) as i64;
+    let (days, secs) = (secs / 86400, secs % 86400);
+
+    // 1970-01-01 からの日数を年月日にする。
+    // http://howardhinnant.github.io/date_algorithms.html の civil_from_days
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (
+        format!("\"{} {day:2} {year}\"", MONTHS[month as usize - 1]),
+        format!(
+            "\"{:02}:{:02}:{:02}\"",
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        ),
+    )
+}
+
+// #if の式の値。intmax_t か uintmax_t として計算する
+#[derive(Clone, Copy)]
+struct CondValue {
+    value: i64,
+    is_unsigned: bool,
+}
+
+impl CondValue {
+    fn int(value: bool) -> Self {
+        Self {
+            value: i64::from(value),
+            is_unsigned: false,
+        }
+    }
+}
+
+// #if の式を評価する。マクロ展開の後に残った識別子は 0 として扱う
+struct CondExpr<'a, 'src> {
+    preprocessor: &'a Preprocessor<'src>,
+    tokens: Vec<Token>,
+    cursor: usize,
+    directive: &'a Token,
+    // && や || や ?: で評価されない側を読んでいる深さ。0 でなければゼロ除算をエラーにしない
+    unevaluated: usize,
+}
+
+impl CondExpr<'_, '_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.cursor)
+    }
+
+    fn consume(&mut self, op: &str) -> bool {
+        if self
+            .peek()
+            .is_some_and(|token| self.preprocessor.is_equal(token, op))
+        {
+            self.cursor += 1;
+            return true;
+        }
+        false
+    }
+
+    // 次のトークンが ops のどれかなら読んで返す
+    fn consume_any(&mut self, ops: &[&'static str]) -> Option<(&'static str, Token)> {
+        let token = self.peek()?;
+        let op = ops
+            .iter()
+            .find(|op| self.preprocessor.is_equal(token, op))?;
+        let token = token.clone();
+        self.cursor += 1;
+        Some((op, token))
+    }
+
+    fn error(&self, message: &str) -> ! {
+        self.preprocessor
+            .error_at(self.peek().unwrap_or(self.directive), message)
+    }
+
+    fn expect(&mut self, op: &str) {
+        if !self.consume(op) {
+            self.error(&format!("expected '{op}'"));
+        }
+    }
+
+    // conditional = logor ("?" conditional ":" conditional)?
+    fn conditional(&mut self) -> CondValue {
+        let cond = self.logor();
+        if !self.consume("?") {
+            return cond;
+        }
+
+        let then = self.with_evaluated(cond.value != 0, Self::conditional);
+        self.expect(":");
+        let els = self.with_evaluated(cond.value == 0, Self::conditional);
+
+        let value = if cond.value != 0 { then } else { els };
+        CondValue {
+            value: value.value,
+            is_unsigned: then.is_unsigned || els.is_unsigned,
+        }
+    }
+
+    // evaluated が偽なら、f で読む部分を評価されないものとして扱う
+    fn with_evaluated(&mut self, evaluated: bool, f: fn(&mut Self) -> CondValue) -> CondValue {
+        if !evaluated {
+            self.unevaluated += 1;
+        }
+        let value = f(self);
+        if !evaluated {
+            self.unevaluated -= 1;
+        }
+        value
+    }
+
+    // logor = logand ("||" logand)*
+    fn logor(&mut self) -> CondValue {
+        let mut lhs = self.logand();
+        while self.consume("||") {
+            let rhs = self.with_evaluated(lhs.value == 0, Self::logand);
+            lhs = CondValue::int(lhs.value != 0 || rhs.value != 0);
+        }
+        lhs
+    }
+
+    // logand = binary ("&&" binary)*
+    fn logand(&mut self) -> CondValue {
+        let mut lhs = self.binary(0);
+        while self.consume("&&") {
+            let rhs = self.with_evaluated(lhs.value != 0, |expr| expr.binary(0));
+            lhs = CondValue::int(lhs.value != 0 && rhs.value != 0);
+        }
+        lhs
+    }
+
+    // | から * までの二項演算子を、優先順位の低いものから順に読む
+    fn binary(&mut self, level: usize) -> CondValue {
+        const LEVELS: [&[&str]; 8] = [
+            &["|"],
+            &["^"],
+            &["&"],
+            &["==", "!="],
+            &["<", "<=", ">", ">="],
+            &["<<", ">>"],
+            &["+", "-"],
+            &["*", "/", "%"],
+        ];
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+
+        let mut lhs = self.binary(level + 1);
+        while let Some((op, token)) = self.consume_any(LEVELS[level]) {
+            let rhs = self.binary(level + 1);
+            lhs = self.eval_binary(op, &token, lhs, rhs);
+        }
+        lhs
+    }
+
+    fn eval_binary(&self, op: &str, token: &Token, lhs: CondValue, rhs: CondValue) -> CondValue {
+        // 通常の算術変換。どちらかが符号なしなら符号なしで計算する
+        let is_unsigned = lhs.is_unsigned || rhs.is_unsigned;
+        let (l, r) = (lhs.value, rhs.value);
+        let value = match op {
+            "*" => l.wrapping_mul(r),
+            "/" | "%" if r == 0 => {
+                if self.unevaluated == 0 {
+                    self.preprocessor.error_at(token, "division by zero in #if");
+                }
+                0
+            }
+            "/" if is_unsigned => ((l as u64) / (r as u64)) as i64,
+            "/" => l.wrapping_div(r),
+            "%" if is_unsigned => ((l as u64) % (r as u64)) as i64,
+            "%" => l.wrapping_rem(r),
+            "+" => l.wrapping_add(r),
+            "-" => l.wrapping_sub(r),
+            // シフトの結果は左辺の型になる
+            "<<" => {
+                return CondValue {
+                    value: l.wrapping_shl(r as u32),
+                    is_unsigned: lhs.is_unsigned,
+                };
+            }
+            ">>" => {
+                return CondValue {
+                    value: if lhs.is_unsigned {
+                        (l as u64).wrapping_shr(r as u32) as i64
+                    } else {
+                        l.wrapping_shr(r as u32)
+                    },
+                    is_unsigned: lhs.is_unsigned,
+                };
+            }
+            "==" => return CondValue::int(l == r),
+            "!=" => return CondValue::int(l != r),
+            "<" | "<=" | ">" | ">=" => {
+                let ordering = if is_unsigned {
+                    (l as u64).cmp(&(r as u64))
+                } else {
+                    l.cmp(&r)
+                };
+                return CondValue::int(match op {
+                    "<" => ordering.is_lt(),
+                    "<=" => ordering.is_le(),
+                    ">" => ordering.is_gt(),
+                    _ => ordering.is_ge(),
+                });
+            }
+            "&" => l & r,
+            "^" => l ^ r,
+            "|" => l | r,
+            _ => unreachable!("unknown operator {op}"),
+        };
+        CondValue { value, is_unsigned }
+    }
+
+    // unary = ("+" | "-" | "!" | "~") unary | primary
+    fn unary(&mut self) -> CondValue {
+        if self.consume("+") {
+            return self.unary();
+        }
+        if self.consume("-") {
+            let value = self.unary();
+            return CondValue {
+                value: value.value.wrapping_neg(),
+                ..value
+            };
+        }
+        if self.consume("!") {
+            return CondValue::int(self.unary().value == 0);
+        }
+        if self.consume("~") {
+            let value = self.unary();
+            return CondValue {
+                value: !value.value,
+                ..value
+            };
+        }
+        self.primary()
+    }
+
+    // primary = "(" conditional ")" | num | char | ident
+    fn primary(&mut self) -> CondValue {
+        if self.consume("(") {
+            let value = self.conditional();
+            self.expect(")");
+            return value;
+        }
+
+        let Some(token) = self.peek() else {
+            self.error("expected an expression");
+        };
+        let kind = match token.kind {
+            TokenKind::PPNum => convert_pp_number(self.preprocessor.source_map, token),
+            ref kind => kind.clone(),
+        };
+        let value = match kind {
+            // 接尾辞に U がなくても、intmax_t に収まらなければ uintmax_t になる
+            TokenKind::Num { value, .. } => CondValue {
+                value,
+                is_unsigned: value < 0 || self.preprocessor.text(token).contains(['u', 'U']),
+            },
+            TokenKind::Char(c) => CondValue {
+                value: i64::from(u32::from(c)),
+                is_unsigned: false,
+            },
+            TokenKind::Ident => CondValue::int(false),
+            TokenKind::Float { .. } => self.error("floating constant in preprocessor expression"),
+            _ => self.error("expected an expression"),
+        };
+        self.cursor += 1;
+        value
+    }
+}
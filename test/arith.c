@@ -165,6 +165,183 @@ int t22() {
   *p;
 }
 
+int t23() {
+  unsigned x = 3000000000;
+  unsigned y = 2;
+  return x / y == 1500000000;
+}
+
+int t24() {
+  unsigned x = 7;
+  return x % 3;
+}
+
+int t25() {
+  unsigned x = 0;
+  x = x - 1;
+  return x == 4294967295;
+}
+
+int t26() {
+  unsigned long x = 0;
+  x = x - 1;
+  return x / 2 == 9223372036854775807;
+}
+
+int t27() {
+  unsigned x = 4000000000;
+  unsigned y = 3000000000;
+  return x + y == 2705032704;
+}
+
+int t28() {
+  unsigned x = 65536;
+  return x * x == 0;
+}
+
+int t29() {
+  int x = 0;
+  int y = 0;
+  1 ? (x = 1) : (y = 1);
+  0 ? (x = x + 10) : (y = y + 10);
+  return x * 100 + y;
+}
+
+int t30() {
+  int a[3];
+  int *p = 0;
+  a[1] = 7;
+  p = p ? p : a;
+  return p[1];
+}
+
+int t31() {
+  char *s = 1 ? "abc" : 0;
+  return s[2];
+}
+
+int t32() {
+  struct {
+    int a;
+    int b;
+  } x, y, z;
+  x.a = 1;
+  x.b = 2;
+  y.a = 3;
+  y.b = 4;
+  z = 0 ? x : y;
+  return z.a * 10 + z.b;
+}
+
+int t33() {
+  int i = 5;
+  void *p = &i;
+  int *q = 1 ? p : &i;
+  return *q;
+}
+
+int t34() {
+  int x = 2;
+  return x > 1 ? x > 2 ? 3 : 2 : 1;
+}
+
+int t35() {
+  enum { A = 1 ? 5 : 6, B = 0 ? 7 : 8 };
+  int arr[A > B ? 1 : 3];
+  return A * 100 + B * 10 + sizeof(arr) / sizeof(int);
+}
+
+int t36() {
+  int i = 13;
+  i %= 5;
+  return i;
+}
+
+int t37() {
+  int i = 6;
+  i &= 3;
+  return i;
+}
+
+int t38() {
+  int i = 6;
+  i |= 3;
+  return i;
+}
+
+int t39() {
+  int i = 15;
+  i ^= 5;
+  return i;
+}
+
+int t40() {
+  int i = 1;
+  i <<= 6;
+  return i;
+}
+
+int t41() {
+  int i = 64;
+  i >>= 3;
+  return i;
+}
+
+int t42() {
+  char c = -128;
+  c >>= 1;
+  return c;
+}
+
+int t43() {
+  unsigned char c = 255;
+  c <<= 1;
+  return c;
+}
+
+int t44() {
+  long x = 1;
+  x <<= 40;
+  return x >> 38;
+}
+
+int t45() {
+  unsigned x = 4294967295;
+  x >>= 0;
+  return x == 4294967295;
+}
+
+int t46() {
+  int arr[4];
+  int *p = arr;
+  arr[0] = 10;
+  arr[1] = 20;
+  int i = 0;
+  p[i++] |= 1;
+  return arr[0] * 10 + i;
+}
+
+int t47() {
+  struct {
+    int x, y;
+  } a = {1, 2}, b = {3, 4};
+  int c = 0;
+  return (c ? a : b).x * 10 + (c = 1, c ? a : b).y + (c, b).x * 100;
+}
+
+int t48() {
+  int x = 5;
+  int *p = &x;
+  return sizeof(*(p ? p : (void *)0)) * 10 + *(0 ? (void *)0 : p);
+}
+
+int t49() {
+  static unsigned x = (0x80000000u << 1) >> 1;
+  static int y = (1 << 31) >> 31;
+  char a[((0x80000000u << 1) >> 1) + 3];
+  return x * 100 + (y == -1) * 10 + sizeof(a);
+}
+
 int main() {
   ASSERT(0, 0);
   ASSERT(42, 42);
@@ -226,6 +403,94 @@ int main() {
   ASSERT(2, t21());
   ASSERT(2, t22());
 
+  ASSERT(1, -1 < 1);
+  ASSERT(0, -1U < 1);
+  ASSERT(1, 1 < -1U);
+  ASSERT(1, -1U <= -1U);
+  ASSERT(0, -1UL < 1);
+  ASSERT(1, -1 > 0U == 1);
+  ASSERT(-1, -7 / 7);
+  ASSERT(613566755, -7U / 7);
+  ASSERT(3, -1U % 6);
+  ASSERT(1, t23());
+  ASSERT(1, t24());
+  ASSERT(1, t25());
+  ASSERT(1, t26());
+  ASSERT(1, t27());
+  ASSERT(1, t28());
+
+  ASSERT(2, 0 ? 1 : 2);
+  ASSERT(1, 1 ? 1 : 2);
+  ASSERT(-1, 0 ? -2 : -1);
+  ASSERT(-2, 1 ? -2 : -1);
+  ASSERT(4, sizeof(0 ? 1 : 2));
+  ASSERT(8, sizeof(0 ? (long)1 : (int)2));
+  ASSERT(4, sizeof(1 ? 1.0f : 2));
+  ASSERT(8, sizeof(1 ? 1.0 : 2.0f));
+  ASSERT(1, (0 ? -1 : 0U) == 0);
+  ASSERT(1, (1 ? -1 : 0U) > 0);
+  ASSERT(3, 1 ? 3.7 : 1);
+  ASSERT(1, 0.0 ? 2 : 1);
+  ASSERT(110, t29());
+  ASSERT(7, t30());
+  ASSERT(99, t31());
+  ASSERT(34, t32());
+  ASSERT(5, t33());
+  ASSERT(2, t34());
+  ASSERT(583, t35());
+
+  ASSERT(0, 0 & 1);
+  ASSERT(1, 3 & 1);
+  ASSERT(3, 7 & 3);
+  ASSERT(10, -1 & 10);
+  ASSERT(1, 0 | 1);
+  ASSERT(-1, -1 | 0);
+  ASSERT(7, 1 | 6);
+  ASSERT(0, 0 ^ 0);
+  ASSERT(0, 15 ^ 15);
+  ASSERT(-1, ~0);
+  ASSERT(0, ~-1);
+  ASSERT(-5, ~4);
+  ASSERT(1, ~0U == 4294967295);
+  ASSERT(1, ~0UL == 18446744073709551615UL);
+  ASSERT(4, sizeof(~(char)1));
+  ASSERT(1, 1 << 0);
+  ASSERT(8, 1 << 3);
+  ASSERT(10, 5 << 1);
+  ASSERT(2, 5 >> 1);
+  ASSERT(-1, -1 >> 1);
+  ASSERT(-4, -8 >> 1);
+  ASSERT(1, -1U >> 31);
+  ASSERT(2147483647, -1U >> 1);
+  ASSERT(1, (1L << 40) == 1099511627776);
+  ASSERT(1, (-1UL >> 63) == 1);
+  ASSERT(-1, -1L >> 63);
+  ASSERT(4, sizeof(1 << 40L));
+  ASSERT(8, sizeof(1L << 1));
+  ASSERT(3, 1 | 2 ^ 3 & 1);
+  ASSERT(7, 1 + 2 << 1 >> 1 | 4);
+  ASSERT(1, 3 & 1 == 1);
+  ASSERT(0, 0 || 2 & 1);
+  ASSERT(1, 0 || 3 & 1);
+  ASSERT(1, 0 | 2 || 0 ^ 3);
+  ASSERT(0, 2 ^ 2 || 0 | 0);
+  ASSERT(1, 0 || 1 && 4 | 1);
+  ASSERT(0, 0 || 1 && 4 & 1);
+  ASSERT(3, t36());
+  ASSERT(2, t37());
+  ASSERT(7, t38());
+  ASSERT(10, t39());
+  ASSERT(64, t40());
+  ASSERT(8, t41());
+  ASSERT(-64, t42());
+  ASSERT(254, t43());
+  ASSERT(4, t44());
+  ASSERT(1, t45());
+  ASSERT(111, t46());
+  ASSERT(332, t47());
+  ASSERT(45, t48());
+  ASSERT(13, t49());
+
   printf("OK\n");
   return 0;
 }
\ No newline at end of file
@@ -0,0 +1,101 @@
+#include "test.h"
+
+_Bool is_positive(int x) { return x > 0; }
+
+_Bool to_bool(long x) { return x; }
+
+int count_true(_Bool a, _Bool b, _Bool c) { return a + b + c; }
+
+int t1() {
+  _Bool x = 0;
+  return x;
+}
+
+int t2() {
+  _Bool x = 1;
+  return x;
+}
+
+int t3() {
+  _Bool x = 2;
+  return x;
+}
+
+int t4() {
+  _Bool x = -1;
+  return x;
+}
+
+int t5() {
+  char c = 0;
+  _Bool x = c;
+  return x;
+}
+
+int t6() {
+  _Bool x = 256;
+  char c = 256;
+  return x * 10 + c;
+}
+
+int t7() {
+  _Bool x = 0.5;
+  return x;
+}
+
+int t8() {
+  int a = 5;
+  _Bool x = &a;
+  return x;
+}
+
+int t9() {
+  _Bool x = 1;
+  x = x + 1;
+  return x;
+}
+
+int t10() {
+  _Bool flags[3];
+  flags[0] = 10;
+  flags[1] = 0;
+  flags[2] = -3;
+  return flags[0] + flags[1] + flags[2];
+}
+
+int t11() {
+  _Bool x = 1;
+  x++;
+  return x;
+}
+
+int main() {
+  ASSERT(0, t1());
+  ASSERT(1, t2());
+  ASSERT(1, t3());
+  ASSERT(1, t4());
+  ASSERT(0, t5());
+  ASSERT(10, t6());
+  ASSERT(1, t7());
+  ASSERT(1, t8());
+  ASSERT(1, t9());
+  ASSERT(2, t10());
+  ASSERT(1, t11());
+
+  ASSERT(0, (_Bool)0);
+  ASSERT(1, (_Bool)1);
+  ASSERT(1, (_Bool)2);
+  ASSERT(1, (_Bool)(char)256 + 1);
+  ASSERT(1, (_Bool)4294967296);
+  ASSERT(0, (_Bool)0.0);
+  ASSERT(1, (_Bool)0.1f);
+  ASSERT(1, sizeof(_Bool));
+
+  ASSERT(1, is_positive(5));
+  ASSERT(0, is_positive(-5));
+  ASSERT(1, to_bool(1099511627776L));
+  ASSERT(3, count_true(7, -1, 100));
+
+  printf("OK\n");
+  return 0;
+}
@@ -0,0 +1,112 @@
+#include "test.h"
+
+int t1() {
+  int x = 512;
+  *(char *)&x = 1;
+  return x;
+}
+
+int t2() {
+  int x = 5;
+  long y = (long)&x;
+  return *(int *)y;
+}
+
+int t3() {
+  long x = 4294967297;
+  int y = x;
+  return y;
+}
+
+int t4() {
+  int x = -1;
+  long y = x;
+  return y == -1;
+}
+
+int t5() {
+  short x = 65537;
+  return x;
+}
+
+int t6() {
+  long x = 2147483647;
+  x = x + 1;
+  return x > 0;
+}
+
+int t7() {
+  int x = 2147483647;
+  x = x + 1;
+  return x < 0;
+}
+
+int t8() {
+  unsigned char x = 255;
+  return x;
+}
+
+int t9() {
+  unsigned short x = 65535;
+  return x == 65535;
+}
+
+int t10() {
+  unsigned x = -1;
+  long y = x;
+  return y == 4294967295;
+}
+
+int t11() {
+  unsigned char x = -1;
+  int y = x + 1;
+  return y;
+}
+
+int t12() {
+  unsigned x = 4294967295;
+  return x + 1 == 0;
+}
+
+int main() {
+  ASSERT(131585, (int)8590066177);
+  ASSERT(513, (short)8590066177);
+  ASSERT(1, (char)8590066177);
+  ASSERT(1, (long)1);
+  ASSERT(0, (long)&*(int *)0);
+  ASSERT(-1, (char)255);
+  ASSERT(-1, (short)65535);
+  ASSERT(1, sizeof((char)1));
+  ASSERT(2, sizeof((short)1));
+  ASSERT(8, sizeof((long)1));
+  ASSERT(4, sizeof((char)1 + (char)1));
+  ASSERT(8, sizeof((int)1 + (long)1));
+  ASSERT(8, sizeof(4294967296));
+
+  ASSERT(513, t1());
+  ASSERT(5, t2());
+  ASSERT(1, t3());
+  ASSERT(1, t4());
+  ASSERT(1, t5());
+  ASSERT(1, t6());
+  ASSERT(1, t7());
+
+  ASSERT(255, (unsigned char)255);
+  ASSERT(65535, (unsigned short)65535);
+  ASSERT(-1, (signed char)255);
+  ASSERT(1, (unsigned long)-1 > 0);
+  ASSERT(1, (unsigned)-1 > 0);
+  ASSERT(0, (int)(unsigned)-1 > 0);
+  ASSERT(1, (long)(unsigned)-1 == 4294967295);
+  ASSERT(1, (unsigned long)(unsigned char)-1 == 255);
+  ASSERT(1, (long)(signed char)255 == -1);
+  ASSERT(1, (unsigned long)(int)-1 == -1);
+  ASSERT(255, t8());
+  ASSERT(1, t9());
+  ASSERT(1, t10());
+  ASSERT(256, t11());
+  ASSERT(1, t12());
+
+  printf("OK\n");
+  return 0;
+}
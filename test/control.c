@@ -83,6 +83,341 @@ int t12() {
 //   return i;
 // }
 
+int t14() {
+  int i = 0;
+  for (; i < 10; i++) {
+    if (i == 3) break;
+  }
+  return i;
+}
+
+int t15() {
+  int i = 0;
+  while (1) {
+    i++;
+    if (i > 5) break;
+  }
+  return i;
+}
+
+int t16() {
+  int i;
+  int sum = 0;
+  for (i = 0; i < 10; i++) {
+    if (i % 2 == 0) continue;
+    sum = sum + i;
+  }
+  return sum;
+}
+
+int t17() {
+  int i = 0;
+  int j = 0;
+  while (i < 10) {
+    i++;
+    if (i > 5) continue;
+    j++;
+  }
+  return i * 10 + j;
+}
+
+int t18() {
+  int i;
+  int j;
+  int k = 0;
+  for (i = 0; i < 3; i++) {
+    for (j = 0; j < 10; j++) {
+      if (j == 2) break;
+      k++;
+    }
+  }
+  return k * 10 + i;
+}
+
+int t19() {
+  int i;
+  int j;
+  int k = 0;
+  for (i = 0; i < 3; i++) {
+    for (j = 0; j < 4; j++) {
+      if (j == 1) continue;
+      k++;
+    }
+    if (i == 1) continue;
+    k = k + 100;
+  }
+  return k;
+}
+
+int t20() {
+  int i = 0;
+  for (;;) {
+    if (i++ == 7) break;
+    continue;
+  }
+  return i;
+}
+
+int sw(int x) {
+  switch (x) {
+  case 0:
+    return 5;
+  case 1:
+    return 6;
+  case 2:
+    return 7;
+  default:
+    return 8;
+  }
+}
+
+int sw_table(int x) {
+  switch (x) {
+  case -2:
+    return 1;
+  case -1:
+    return 2;
+  case 0:
+    return 3;
+  case 2:
+    return 5;
+  case 3:
+    return 6;
+  case 5:
+    return 8;
+  default:
+    return 0;
+  }
+}
+
+int sw_fallthrough(int x) {
+  int i = 0;
+  switch (x) {
+  case 0:
+    i = i + 1;
+  case 1:
+    i = i + 10;
+  case 2:
+  case 3:
+    i = i + 100;
+    break;
+  case 4:
+    i = i + 1000;
+  }
+  return i;
+}
+
+int count_words(char *s) {
+  int state = 0;
+  int n = 0;
+  for (; *s; s++) {
+    switch (state) {
+    case 0:
+      if (*s != ' ') {
+        state = 1;
+        n++;
+      }
+      break;
+    case 1:
+      if (*s == ' ') state = 0;
+      break;
+    }
+  }
+  return n;
+}
+
+int t21() {
+  int i = 0;
+  switch (3) {
+  case 5 - 2:
+    i = 5;
+    break;
+  case 1 + 1:
+    i = 6;
+    break;
+  }
+  return i;
+}
+
+int t22() {
+  int i = 0;
+  switch (9) {
+  case 0:
+    i = 5;
+    break;
+  case 1:
+    i = 6;
+    break;
+  }
+  return i;
+}
+
+int t23() {
+  int i = 0;
+  int j = 0;
+  for (; i < 10; i++) {
+    switch (i) {
+    case 3:
+      continue;
+    case 5:
+      break;
+    default:
+      j++;
+    }
+    if (i == 7) break;
+  }
+  return i * 10 + j;
+}
+
+int t24() {
+  int i = 0;
+  switch (1) {
+  case 1:
+    switch (2) {
+    case 2:
+      i = i + 1;
+      break;
+    default:
+      i = i + 10;
+    }
+    i = i + 100;
+    break;
+  case 2:
+    i = i + 1000;
+  }
+  return i;
+}
+
+int t25() {
+  char c = 'b';
+  switch (c) {
+  case 'a':
+    return 1;
+  case 'b':
+    return 2;
+  }
+  return 0;
+}
+
+int t26() {
+  unsigned x = -1;
+  switch (x) {
+  case -1:
+    return 1;
+  }
+  return 0;
+}
+
+int t27() {
+  long x = 10000000000;
+  switch (x) {
+  case 10000000000:
+    return 1;
+  case 1410065408:
+    return 2;
+  }
+  return 0;
+}
+
+int t28() {
+  int i = 0;
+  switch (1) {
+    i = 5;
+  default:
+    i = i + 1;
+  }
+  return i;
+}
+
+int t29() {
+  int i = 0;
+  goto a;
+a:
+  i++;
+b:
+  i++;
+c:
+  i++;
+  return i;
+}
+
+int t30() {
+  int i = 0;
+  goto e;
+d:
+  i++;
+e:
+  i++;
+f:
+  i++;
+  return i;
+}
+
+int t31() {
+  int i = 0;
+  int sum = 0;
+loop:
+  if (i >= 10) goto done;
+  sum = sum + i;
+  i++;
+  goto loop;
+done:
+  return sum;
+}
+
+int open_all(int fail_at) {
+  int opened = 0;
+  if (fail_at == 1) goto fail;
+  opened++;
+  if (fail_at == 2) goto fail_close;
+  opened++;
+  return opened;
+
+fail_close:
+  opened--;
+fail:
+  return -1 - opened;
+}
+
+int t32() {
+  int i = 0;
+  int j = 0;
+  do {
+    j++;
+  } while (i++ < 5);
+  return j;
+}
+
+int t33() {
+  int i = 0;
+  do
+    i = i + 10;
+  while (0);
+  return i;
+}
+
+int t34() {
+  int i = 0;
+  int j = 0;
+  do {
+    i++;
+    if (i % 2) continue;
+    if (i > 7) break;
+    j = j + i;
+  } while (i < 100);
+  return i * 100 + j;
+}
+
+int t35() {
+  double x = 0.5;
+  int i = 0;
+  do {
+    i++;
+    x = x - 0.25;
+  } while (x);
+  return i;
+}
+
 int main() {
   ASSERT(3, t1());
   ASSERT(3, t2());
@@ -129,6 +464,55 @@ int main() {
 
   ASSERT(55, t12());
   // ASSERT(3, t13());
+  ASSERT(3, t14());
+  ASSERT(6, t15());
+  ASSERT(25, t16());
+  ASSERT(105, t17());
+  ASSERT(63, t18());
+  ASSERT(209, t19());
+  ASSERT(8, t20());
+
+  ASSERT(5, sw(0));
+  ASSERT(6, sw(1));
+  ASSERT(7, sw(2));
+  ASSERT(8, sw(3));
+  ASSERT(8, sw(-1));
+  ASSERT(0, sw_table(-3));
+  ASSERT(1, sw_table(-2));
+  ASSERT(2, sw_table(-1));
+  ASSERT(3, sw_table(0));
+  ASSERT(0, sw_table(1));
+  ASSERT(5, sw_table(2));
+  ASSERT(6, sw_table(3));
+  ASSERT(0, sw_table(4));
+  ASSERT(8, sw_table(5));
+  ASSERT(0, sw_table(6));
+  ASSERT(0, sw_table(-2147483647));
+  ASSERT(0, sw_table(2147483647));
+  ASSERT(111, sw_fallthrough(0));
+  ASSERT(110, sw_fallthrough(1));
+  ASSERT(100, sw_fallthrough(3));
+  ASSERT(1000, sw_fallthrough(4));
+  ASSERT(0, sw_fallthrough(5));
+  ASSERT(3, count_words("  ab cd   efg "));
+  ASSERT(5, t21());
+  ASSERT(0, t22());
+  ASSERT(76, t23());
+  ASSERT(101, t24());
+  ASSERT(2, t25());
+  ASSERT(1, t26());
+  ASSERT(1, t27());
+  ASSERT(1, t28());
+  ASSERT(3, t29());
+  ASSERT(2, t30());
+  ASSERT(45, t31());
+  ASSERT(-1, open_all(1));
+  ASSERT(-1, open_all(2));
+  ASSERT(2, open_all(3));
+  ASSERT(6, t32());
+  ASSERT(10, t33());
+  ASSERT(812, t34());
+  ASSERT(2, t35());
 
   printf("OK\n");
   return 0;
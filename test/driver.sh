@@ -1,6 +1,6 @@
 
 #!/bin/sh
-alias gakicc='RUSTFLAGS=-Awarnings cargo run -q --'
+alias gakicc='RUSTFLAGS=-Awarnings cargo run -q -- -Iinclude'
 
 tmp=`mktemp -d /tmp/gakicc-test-XXXXXX`
 trap 'rm -rf $tmp' INT TERM HUP EXIT
@@ -25,4 +25,114 @@ check -o
 gakicc --help 2>&1 | grep -q gakicc
 check --help
 
+# 入力ファイルがなければ使い方を表示して失敗する
+gakicc 2>&1 | grep -q Usage
+check 'no input files'
+! gakicc 2> /dev/null
+check 'no input files status'
+
+# 同梱のヘッダは実行ファイルの隣の include から探す
+cargo build -q 2> /dev/null
+mkdir -p $tmp/bin
+cp target/debug/chibicc-rs-riscv $tmp/bin/gakicc
+cp -r include $tmp/bin/include
+echo "#include <stdbool.h>
+bool" | $tmp/bin/gakicc -E - | grep -q _Bool
+check 'bundled headers'
+
+# -E
+echo "#define FOO 3
+FOO" | gakicc -E - | grep -q 3
+check -E
+
+# -I
+mkdir -p $tmp/dir
+echo foo > $tmp/dir/i-option-test
+echo "#include \"i-option-test\"" | gakicc -I$tmp/dir -E - | grep -q foo
+check -I
+
+# #include は取り込み元のファイルのディレクトリから探す
+mkdir -p $tmp/sub
+echo "#include \"inc.h\"" > $tmp/sub/main.c
+echo bar > $tmp/sub/inc.h
+gakicc -E $tmp/sub/main.c | grep -q bar
+check '#include'
+
+# #error
+echo "#error foo bar" | gakicc -E - 2>&1 | grep -q "#error foo bar"
+check '#error'
+
+# インクルードガードのあるヘッダは 2 回目は何も取り込まない
+printf "#ifndef GUARD_H\n#define GUARD_H\nguarded\n#endif\n" > $tmp/guard.h
+printf "#include \"guard.h\"\n#include \"guard.h\"\n" > $tmp/guard.c
+[ "$(gakicc -E $tmp/guard.c | grep -c guarded)" = 1 ]
+check 'include guard'
+
+# 読み飛ばすグループの中なら、閉じていない引用符があってもエラーにしない
+printf "#if 0\ndon't\n#endif\nint x;\n" | gakicc -o $tmp/out -
+check 'skipped quote'
+
+# 残っていればエラーにする
+printf "int x = 'a;\n" | gakicc -o $tmp/out - 2>&1 | grep -q "unclosed char literal"
+check 'unclosed quote'
+
+# 列挙定数の値は int の範囲に収まらなければならない
+echo "enum { A = 2147483648 };" | gakicc -o $tmp/out - 2>&1 | grep -q "outside the range of int"
+check 'enumerator range'
+echo "enum { A = 2147483647, B };" | gakicc -o $tmp/out - 2>&1 | grep -q "outside the range of int"
+check 'enumerator overflow'
+
+# ブロック内の extern 宣言はそのブロックの中でだけ見える
+printf "int f() {\n  { extern int x; }\n  return x;\n}\n" | gakicc -o $tmp/out - 2>&1 | grep -q "undefined variable"
+check 'block extern scope'
+
+# (void) 以外で void の仮引数は作れない
+echo "int f(int, void);" | gakicc -o $tmp/out - 2>&1 | grep -q "parameter declared void"
+check 'void parameter'
+
+# 関数と変数で同じ名前は使えず、関数の定義は一つだけ
+printf "int f;\nint f() { return 0; }\n" | gakicc -o $tmp/out - 2>&1 | grep -q "different kind of symbol"
+check 'variable then function'
+printf "int f() { return 0; }\nint f;\n" | gakicc -o $tmp/out - 2>&1 | grep -q "different kind of symbol"
+check 'function then variable'
+printf "int f() { return 0; }\nint f() { return 1; }\n" | gakicc -o $tmp/out - 2>&1 | grep -q "redefinition of 'f'"
+check 'function redefinition'
+
+# 同じ変数の宣言で結合が食い違ってはいけない (後の extern は先の結合を引き継ぐ)
+printf "int x;\nstatic int x;\n" | gakicc -o $tmp/out - 2>&1 | grep -q "static declaration of 'x' follows non-static"
+check 'static after non-static'
+printf "static int x;\nint x;\n" | gakicc -o $tmp/out - 2>&1 | grep -q "non-static declaration of 'x' follows static"
+check 'non-static after static'
+
+# 宣言子の名前は typedef の型に残らない
+printf "typedef int T;\nT a;\nint f(T) { return a; }\n" | gakicc -o $tmp/out - 2>&1 | grep -q "parameter name omitted"
+check 'typedef keeps no name'
+
+# 長さが省略された配列の初期化子は一度だけ読むので、文字列リテラルも一つずつしか作らない
+printf 'char *a[] = {"abc", "def"};\nstruct { char *p; int x; } b[] = {"ghi", 1, "jkl", 2};\n' | gakicc -o $tmp/out -
+[ `grep -c '\.string' $tmp/out` -eq 4 ]
+check 'unsized array strings'
+
+# -dM
+gakicc -dM -E -o $tmp/macros $tmp/empty.c
+grep -q "#define __gakicc__ 1" $tmp/macros
+check -dM
+grep -q "#define __riscv_xlen 64" $tmp/macros
+check '-dM __riscv_xlen'
+
+# エラーの位置はインクルードしたファイルの行と列で表示する
+printf "int x = 1;\nint y = ;\n" > $tmp/hdr.h
+echo "#include \"hdr.h\"" > $tmp/line.c
+gakicc -o $tmp/out $tmp/line.c 2>&1 | grep -q "hdr.h:2:9"
+check 'error location'
+
+# マクロの展開結果のエラーは、マクロを書いた位置で表示する
+printf "#define CAT(a, b) a ## b\nint main() {\n  return CAT(fo, o);\n}\n" > $tmp/paste.c
+gakicc -o $tmp/out $tmp/paste.c 2>&1 | grep -q "paste.c:3:10"
+check 'macro error location'
+
+# GCC の -E が出力する行マーカー
+printf "# 12 \"foo.h\"\nint y = ;\n" | gakicc -o $tmp/out - 2>&1 | grep -q "foo.h:12:9"
+check 'line marker'
+
 echo -e "OK\n"
\ No newline at end of file
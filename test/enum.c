@@ -0,0 +1,91 @@
+#include "test.h"
+
+enum Color { RED, GREEN = 5, BLUE };
+enum { ZERO, ONE, TWO, } g;
+
+int color_value(enum Color c) { return c; }
+
+int t1() {
+  enum { zero, one, two };
+  return zero + one + two;
+}
+
+int t2() {
+  enum { five = 5, six, seven };
+  return seven;
+}
+
+int t3() {
+  enum { zero, five = 5, three = 3, four };
+  return four;
+}
+
+int t4() {
+  enum { zero, one, two } x;
+  return sizeof(x);
+}
+
+int t5() {
+  enum t { zero, one, two };
+  enum t y;
+  return sizeof(y);
+}
+
+int t6() {
+  enum t { A = 10 };
+  {
+    enum { A = 20 };
+    return A;
+  }
+}
+
+int t7() {
+  enum Color c = BLUE;
+  c = c + 1;
+  return c;
+}
+
+int t8() {
+  int arr[TWO + 1];
+  return sizeof(arr);
+}
+
+int t9() {
+  enum { N = 2 * 3 + (7 - 1) / 2, M = N - 1, K = N > M };
+  return N * 100 + M * 10 + K;
+}
+
+int t10() {
+  int RED = 7;
+  return RED;
+}
+
+int t11() {
+  enum { C = 0xffffffffu + 1, D = -1 - 2147483647 };
+  int a[0xffffffffu + 2];
+  return (C == 0) * 1000 + sizeof(C) * 100 + sizeof(a) * 10 + (D < 0);
+}
+
+int main() {
+  ASSERT(3, t1());
+  ASSERT(7, t2());
+  ASSERT(4, t3());
+  ASSERT(4, t4());
+  ASSERT(4, t5());
+  ASSERT(20, t6());
+  ASSERT(7, t7());
+  ASSERT(12, t8());
+  ASSERT(981, t9());
+  ASSERT(7, t10());
+  ASSERT(1441, t11());
+  ASSERT(0, RED);
+  ASSERT(5, GREEN);
+  ASSERT(6, BLUE);
+  ASSERT(6, color_value(BLUE));
+  ASSERT(4, sizeof(enum Color));
+  ASSERT(4, sizeof(g));
+  ASSERT(2, TWO);
+
+  printf("OK\n");
+  return 0;
+}
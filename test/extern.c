@@ -0,0 +1,106 @@
+#include "test.h"
+
+extern int ext1;
+extern int *ext2;
+extern int ext_arr[3];
+int call_common_static();
+
+int tent1;
+int tent1;
+int tent1 = 7;
+int tent1;
+int tent2[];
+int tent2[3];
+extern int tent3;
+int tent3;
+extern int tent3;
+static int tent4;
+static int tent4;
+extern int tent4;
+extern char *tent5;
+char *tent5 = "abc";
+extern char *tent5;
+extern int ext3;
+
+static int static_shadow = 1;
+static int static_fn() { return 2; }
+
+int t1() {
+  ext1 = 5;
+  return ext1;
+}
+
+int t2() {
+  ext2 = &ext1;
+  return *ext2;
+}
+
+int t3() {
+  extern int ext3;
+  return ext3;
+}
+
+int t4() { return ext_arr[0] + ext_arr[1] + ext_arr[2]; }
+
+int t5() { return sizeof(ext_arr); }
+
+int t6() { return static_shadow + static_fn(); }
+
+int t7() {
+  tent1 = tent1 + 1;
+  return tent1;
+}
+
+int t8() {
+  tent2[2] = 5;
+  return sizeof(tent2) + tent2[0] + tent2[2];
+}
+
+int t9() {
+  tent4 = tent4 + 2;
+  return tent3 + tent4;
+}
+
+int t10() {
+  int ext3 = 100;
+  int x;
+  {
+    extern int ext3;
+    x = ext3;
+  }
+  return x + ext3;
+}
+
+int t11() {
+  extern int ext_arr[];
+  return sizeof(ext_arr);
+}
+
+int t12() {
+  extern int late_def;
+  return late_def;
+}
+
+int late_def = 9;
+
+int main() {
+  ASSERT(5, t1());
+  ASSERT(5, t2());
+  ASSERT(5, t3());
+  ASSERT(6, t4());
+  ASSERT(12, t5());
+  ASSERT(3, t6());
+  ASSERT(18, call_common_static());
+  ASSERT(7, tent1);
+  ASSERT(8, t7());
+  ASSERT(17, t8());
+  ASSERT(2, t9());
+  ASSERT(4, t9());
+  ASSERT('c', tent5[2]);
+  ASSERT(105, t10());
+  ASSERT(12, t11());
+  ASSERT(9, t12());
+
+  printf("OK\n");
+  return 0;
+}
@@ -0,0 +1,176 @@
+#include "test.h"
+
+int sprintf(char *buf, char *fmt, ...);
+int strcmp(char *p, char *q);
+
+double add_double(double x, double y) { return x + y; }
+float add_float(float x, float y) { return x + y; }
+
+double add_double3(double x, double y, double z) { return x + y + z; }
+
+double mixed(int a, double b, long c, float d) { return a * b + c * d; }
+
+double many_double(double a, double b, double c, double d, double e, double f,
+                   double g, double h) {
+  return a - b + c - d + e - f + g - h;
+}
+
+double sub10_double(double a, double b, double c, double d, double e,
+                    double f, double g, double h, double i, double j) {
+  return a - b - c - d - e - f - g - h - i - j;
+}
+
+double last_double(long a, long b, long c, long d, long e, long f, long g,
+                   long h, double i, double j, double k, double l, double m,
+                   double n, double o, double p, float q, double r) {
+  return (q - r) * 10 + p;
+}
+
+float half(float x) { return x / 2; }
+
+int t1() {
+  float x = 1.5;
+  float y = 2.25;
+  return (x + y) * 4;
+}
+
+int t2() {
+  double x = 0.1;
+  double y = 0.2;
+  return x + y > 0.3;
+}
+
+int t3() {
+  double x = 10;
+  x /= 4;
+  return x * 100;
+}
+
+int t4() {
+  double x = 3.9;
+  int i = 0;
+  for (; x > 0; x = x - 1)
+    i++;
+  return i;
+}
+
+int t5() {
+  float f = 0.0;
+  if (f)
+    return 1;
+  if (!f)
+    return 2;
+  return 3;
+}
+
+int t6() {
+  double a[3];
+  a[0] = 1.5;
+  a[1] = 2.5;
+  a[2] = a[0] * a[1];
+  return a[2] * 4;
+}
+
+int t7() {
+  double x = 1e300;
+  return x * x > 1e300;
+}
+
+int t8() {
+  char buf[32];
+  sprintf(buf, "%.3f", 3.14159);
+  return strcmp(buf, "3.142") == 0;
+}
+
+int t9() {
+  char buf[32];
+  float f = 2.5f;
+  sprintf(buf, "%f %d %g", f, 7, add_double(1.25, 2));
+  return strcmp(buf, "2.500000 7 3.25") == 0;
+}
+
+unsigned float_to_uint(float x) { return x; }
+
+// Out-of-range conversions truncate like the runtime conversion does.
+long g_fold = (char)300.0 * 10000 + (unsigned short)70000.0;
+
+int t10() {
+  unsigned long x = float_to_uint(3000000000.0f);
+  unsigned y = 3000000000.0;
+  float f = 3000000000.0f;
+  return (x == 3000000000) + (y == 3000000000) * 10 +
+         ((unsigned long)(unsigned)f == 3000000000) * 100;
+}
+
+int main() {
+  ASSERT(35, (int)(12.0 * 3 - 0.5 + 0.5 - 1));
+  ASSERT(3, 3.9);
+  ASSERT(-3, -3.9);
+  ASSERT(0, 0.5);
+  ASSERT(5, (char)5.5);
+  ASSERT(-5, (short)-5.5);
+  ASSERT(4, (unsigned)4.75);
+  ASSERT(1, (long)1.99999);
+  ASSERT(0, (unsigned char)0.2);
+  ASSERT(1, 2e3 == 2000);
+  ASSERT(1, .5 == 0.5);
+  ASSERT(1, 1.5e-1 == 0.15);
+  ASSERT(1, 1.f == 1.0);
+
+  ASSERT(4, sizeof(float));
+  ASSERT(8, sizeof(double));
+  ASSERT(4, sizeof(1.5f));
+  ASSERT(8, sizeof(1.5));
+  ASSERT(4, sizeof(1.5f * 2));
+  ASSERT(8, sizeof(1.5f * 2.0));
+  ASSERT(8, sizeof(1 + 1.0));
+  ASSERT(4, sizeof((char)1 + 1.0f));
+  ASSERT(8, sizeof(1L + 1.0));
+
+  ASSERT(1, 1.5 < 2.5);
+  ASSERT(0, 2.5 < 1.5);
+  ASSERT(1, 1.5 <= 1.5);
+  ASSERT(1, 2.5 > 1.5);
+  ASSERT(1, 2.5 >= 2.5);
+  ASSERT(1, 1.5 == 1.5);
+  ASSERT(0, 1.5 != 1.5);
+  ASSERT(1, 1.5f < 2);
+  ASSERT(1, -1 < 0.5);
+  ASSERT(0, 4294967295U < 0.5);
+  ASSERT(1, 0.1f != 0.1);
+
+  ASSERT(1, 0.5 && 1);
+  ASSERT(0, 0.0 && 1);
+  ASSERT(1, 0.0 || 0.5);
+  ASSERT(0, 0.0 || 0.0f);
+  ASSERT(0, !0.5);
+  ASSERT(1, !0.0);
+
+  ASSERT(15, t1());
+  ASSERT(1, t2());
+  ASSERT(250, t3());
+  ASSERT(4, t4());
+  ASSERT(2, t5());
+  ASSERT(15, t6());
+  ASSERT(1, t7());
+
+  ASSERT(6, add_double(2.3, 3.7));
+  ASSERT(6, add_float(2.3, 3.7));
+  ASSERT(6, add_double3(1.5, 2.25, 2.25));
+  ASSERT(17, mixed(3, 2.5, 4, 2.5));
+  ASSERT(-4, many_double(1, 2, 3, 4, 5, 6, 7, 8));
+  ASSERT(10, sub10_double(100, 1, 2, 3, 4, 5, 6, 7, 8, 54));
+  ASSERT(1, sub10_double(1.5, 0.25, 0, 0, 0, 0, 0, 0, 0.125, 0.125) == 1);
+  ASSERT(22, last_double(0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2, 2.5,
+                         0.5));
+  ASSERT(3, half(7));
+  ASSERT(1, half(3) == 1.5);
+
+  ASSERT(1, t8());
+  ASSERT(1, t9());
+  ASSERT(111, t10());
+  ASSERT(444464, g_fold);
+
+  printf("OK\n");
+  return 0;
+}
@@ -26,6 +26,52 @@ int t1() { sub_char(7, 3, 3); }
 
 int k(int x, int y) {}
 
+short sub_short(short a, short b, short c) { return a - b - c; }
+
+long sub_long(long a, long b, long c) { return a - b - c; }
+
+int ret_int_from_long() { return 8590066177; }
+
+long add_long(long x, long y) { return x + y; }
+
+long long_mul(long x, long y) { return x * y; }
+static int static_add(int x, int y) { return x + y; }
+
+static int static_decl(int x);
+
+int call_static_decl() { return static_decl(3); }
+
+int static_decl(int x) { return x * 2; }
+
+int sub10(int a, int b, int c, int d, int e, int f, int g, int h, int i,
+          int j) {
+  return a - b - c - d - e - f - g - h - i - j;
+}
+
+long last_of12(char a, short b, int c, long d, int e, int f, int g, int h,
+               char i, short j, int k, long l) {
+  return i * 1000 + j * 100 + k * 10 + l;
+}
+
+int sum_row(int (*row)[3]);
+int sum_row(int (*)[3]);
+int sum_row(int (*row)[3]) { return (*row)[0] + (*row)[1] + (*row)[2]; }
+
+int with_callback(int (*cb)(int), int x);
+int with_callback(int (*)(int), int);
+int with_callback(int (*cb)(int), int x) { return sizeof(cb) + x; }
+
+int t2() {
+  int a[2][3] = {{1, 2, 3}, {4, 5, 6}};
+  return sum_row(a + 1) * 100 + with_callback(0, 2);
+}
+
+int no_args(void);
+int no_args(void) { return 7; }
+
+typedef void VOID;
+int no_args2(VOID) { return 3; }
+
 int main() {
   ASSERT(3, ret3());
   ASSERT(8, add2(3, 5));
@@ -41,6 +87,22 @@ int main() {
 
   ASSERT(1, t1());
 
+  ASSERT(1, sub_short(7, 3, 3));
+  ASSERT(1, sub_long(7, 3, 3));
+  ASSERT(131585, ret_int_from_long());
+  ASSERT(1, add_long(4294967295, 2) == 4294967297);
+  ASSERT(1, long_mul(1000000, 1000000) == 1000000000000);
+  ASSERT(0, long_mul(1000000, 1000000) < 0);
+  ASSERT(7, static_add(3, 4));
+  ASSERT(6, call_static_decl());
+  ASSERT(10, sub10(100, 1, 2, 3, 4, 5, 6, 7, 8, 54));
+  ASSERT(-35, sub10(sub10(55, 1, 2, 3, 4, 5, 6, 7, 8, 9), 1, 2, 3, 4, 5, 6, 7,
+                  8, 9 - sub10(9, 1, 1, 1, 1, 1, 1, 1, 1, 1)));
+  ASSERT(1510, t2());
+  ASSERT(7, no_args());
+  ASSERT(73, no_args() * 10 + no_args2());
+  ASSERT(4321, last_of12(0, 0, 0, 0, 0, 0, 0, 0, 4, 3, 2, 1));
+
   printf("OK\n");
   return 0;
 }
\ No newline at end of file
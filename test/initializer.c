@@ -0,0 +1,399 @@
+#include "test.h"
+
+int strcmp(const char *, const char *);
+
+char g3 = 3;
+short g4 = 4;
+int g5 = 5;
+long g6 = 6;
+int g9[3] = {0, 1, 2};
+struct {
+  char a;
+  int b;
+} g11[2] = {{1, 2}, {3, 4}};
+struct {
+  int a[2];
+} g12[2] = {{{1, 2}}};
+union {
+  int a;
+  char b[8];
+} g13[2] = {0x01020304, 0x05060708};
+char g17[] = "foobar";
+char g18[10] = "foobar";
+char g19[3] = "foobar";
+char *g20 = g17 + 0;
+char *g21 = g17 + 3;
+char *g23[] = {g17 + 0, g17 + 3, g17 - 3};
+int g24 = 3;
+int *g25 = &g24;
+int g26[3] = {1, 2, 3};
+int *g27 = g26 + 1;
+int *g28 = &g11[1].b;
+long g29 = (long)(long)g26;
+struct {
+  struct {
+    int a[3];
+  } a;
+} g30 = {{{1, 2, 3}}};
+int *g31 = g30.a.a;
+struct {
+  int a[2];
+} g40[2] = {{1, 2}, 3, 4};
+struct {
+  int a[2];
+} g41[2] = {1, 2, 3, 4};
+char g43[][4] = {'f', 'o', 'o', 0, 'b', 'a', 'r', 0};
+char *g44 = {"foo"};
+double g45 = 1.5;
+float g46 = 2.5f;
+double g47 = 1 + 2.5;
+int g48 = 1 ? 2 : 3;
+_Bool g49 = 256;
+char *g50 = "hi";
+int g51[] = {1, 2, 3,};
+unsigned char g52 = -1;
+long g53 = 1L << 40;
+int g54 = 3.9;
+float g55[] = {1, 2.5, 3};
+int g56[2][3] = {{1, 2}, {4}};
+char g57 = 'a';
+int g58 = sizeof(g26) / sizeof(int);
+struct {
+  char c;
+  long l;
+  short s;
+} g59 = {1, 2, 3};
+int g60[5] = {[2] = 3, 4, [0] = 1};
+int g61[] = {[4] = 5, [1] = 2};
+struct {
+  int a;
+  int b;
+  int c;
+} g62 = {.c = 3, .a = 1};
+union {
+  int a;
+  char b[4];
+} g63 = {.b = {1, 2}};
+struct {
+  int a[3];
+  int b;
+} g64 = {.a[1] = 2, 3, .b = 4};
+
+int t1() {
+  int x[3] = {1, 2, 3};
+  return x[0] * 100 + x[1] * 10 + x[2];
+}
+
+int t2() {
+  int x[2][3] = {{1, 2, 3}, {4, 5, 6}};
+  return x[0][1] * 10 + x[1][2];
+}
+
+int t3() {
+  int x[5] = {1, 2};
+  return x[0] + x[1] + x[2] + x[3] + x[4];
+}
+
+int t4() {
+  int x[2][3] = {{1, 2}};
+  return x[0][1] * 10 + x[1][2];
+}
+
+int t5() {
+  char x[4] = "abc";
+  return x[0] + x[3];
+}
+
+int t6() {
+  char x[] = "hello";
+  return sizeof(x);
+}
+
+int t7() {
+  int x[] = {1, 2, 3, 4};
+  return sizeof(x) + x[3];
+}
+
+int t8() {
+  struct {
+    int a;
+    long b;
+    char c;
+  } x = {1, 2, 3};
+  return x.a * 100 + x.b * 10 + x.c;
+}
+
+int t9() {
+  struct {
+    int a;
+    int b;
+    int c;
+  } x = {1};
+  return x.a + x.b + x.c;
+}
+
+int t10() {
+  struct {
+    int a[2];
+    int b;
+  } x[2] = {1, 2, 3, 4, 5, 6};
+  return x[1].a[0] * 100 + x[1].a[1] * 10 + x[1].b;
+}
+
+int t11() {
+  union {
+    int a;
+    char b[4];
+  } x = {0x01020304};
+  return x.b[0] * 10 + x.b[3];
+}
+
+int t12() {
+  int x[5] = {[3] = 4, 5, [1] = 2};
+  return x[0] * 10000 + x[1] * 1000 + x[2] * 100 + x[3] * 10 + x[4];
+}
+
+int t13() {
+  int x[] = {[6] = 1, [2] = 3};
+  return sizeof(x) / sizeof(int) * 10 + x[2];
+}
+
+int t14() {
+  struct {
+    int a;
+    int b;
+    int c;
+  } x = {.b = 2, 3, .a = 1};
+  return x.a * 100 + x.b * 10 + x.c;
+}
+
+int t15() {
+  struct {
+    int a;
+    struct {
+      int b;
+      int c;
+    } s;
+  } x = {.s.c = 5, .a = 1};
+  return x.a * 100 + x.s.b * 10 + x.s.c;
+}
+
+int t16() {
+  struct {
+    int a;
+    int b;
+  } x[3] = {[1].b = 2, [2] = {3, 4}};
+  return x[0].a + x[0].b * 10 + x[1].a * 100 + x[1].b * 1000 + x[2].b;
+}
+
+int t17() {
+  union {
+    char a;
+    int b;
+  } x = {.b = 0x01020304};
+  return x.b == 0x01020304;
+}
+
+int t18() {
+  int y = 10;
+  int x[3] = {y, y + 1, y * 2};
+  return x[0] + x[1] + x[2];
+}
+
+int t19() {
+  int x[10] = {1, 2, 3};
+  x[9] = 9;
+  int y[10] = {};
+  return x[3] + x[9] + y[0] + y[9];
+}
+
+int t20() {
+  int s = 0;
+  for (int i = 0; i < 3; i++) {
+    int x[3] = {i};
+    s = s + x[0] + x[1] + x[2];
+    x[1] = 100;
+  }
+  return s;
+}
+
+int t21() {
+  char *x[] = {"foo", "bar"};
+  return strcmp(x[1], "bar");
+}
+
+typedef struct {
+  int a;
+  int b;
+} Pair;
+
+int t22() {
+  Pair x = {1, 2};
+  Pair y[2] = {x, {.b = 3}};
+  return y[0].a * 100 + y[0].b * 10 + y[1].b;
+}
+
+int t23() {
+  int x[3][2] = {[1][1] = 4, 5, [0] = {1}};
+  return x[0][0] * 1000 + x[1][1] * 100 + x[2][0] * 10 + x[2][1];
+}
+
+int t24() {
+  int x = {3};
+  double y = 1.5;
+  float z[2] = {2.5, 1};
+  return x + y * 2 + z[0] * 2;
+}
+
+int t25() {
+  struct {
+    int a;
+    char s[4];
+  } p = {7, "ab"}, x[] = {1, "cd", p, [3].s = "ef", 9, "g"};
+  return sizeof(x) / sizeof(x[0]) * 1000 + x[1].a * 100 + x[4].a * 10 +
+         (x[3].s[1] == 'f') + (x[4].s[0] == 'g');
+}
+
+int t26() {
+  int y[][2] = {1, 2, 3, [3] = 4, 5};
+  return sizeof(y) / sizeof(y[0]) * 1000 + y[1][0] * 100 + y[3][1] * 10 +
+         y[1][1];
+}
+
+int main() {
+  ASSERT(3, g3);
+  ASSERT(4, g4);
+  ASSERT(5, g5);
+  ASSERT(6, g6);
+
+  ASSERT(0, g9[0]);
+  ASSERT(1, g9[1]);
+  ASSERT(2, g9[2]);
+
+  ASSERT(1, g11[0].a);
+  ASSERT(2, g11[0].b);
+  ASSERT(3, g11[1].a);
+  ASSERT(4, g11[1].b);
+
+  ASSERT(1, g12[0].a[0]);
+  ASSERT(2, g12[0].a[1]);
+  ASSERT(0, g12[1].a[0]);
+  ASSERT(0, g12[1].a[1]);
+
+  ASSERT(4, g13[0].b[0]);
+  ASSERT(3, g13[0].b[1]);
+  ASSERT(8, g13[1].b[0]);
+  ASSERT(7, g13[1].b[1]);
+
+  ASSERT(7, sizeof(g17));
+  ASSERT(10, sizeof(g18));
+  ASSERT(3, sizeof(g19));
+
+  ASSERT(0, strcmp(g17, "foobar"));
+  ASSERT(0, strcmp(g18, "foobar"));
+  ASSERT(0, g18[9]);
+  ASSERT('o', g19[2]);
+
+  ASSERT(0, strcmp(g20, "foobar"));
+  ASSERT(0, strcmp(g21, "bar"));
+  ASSERT(0, strcmp(g23[0], "foobar"));
+  ASSERT(0, strcmp(g23[1], "bar"));
+  ASSERT(0, strcmp(g23[2] + 3, "foobar"));
+
+  ASSERT(3, g24);
+  ASSERT(3, *g25);
+  ASSERT(2, *g27);
+  ASSERT(4, *g28);
+  ASSERT(1, *(int *)g29);
+
+  ASSERT(1, g31[0]);
+  ASSERT(2, g31[1]);
+  ASSERT(3, g31[2]);
+
+  ASSERT(1, g40[0].a[0]);
+  ASSERT(2, g40[0].a[1]);
+  ASSERT(3, g40[1].a[0]);
+  ASSERT(4, g40[1].a[1]);
+
+  ASSERT(1, g41[0].a[0]);
+  ASSERT(2, g41[0].a[1]);
+  ASSERT(3, g41[1].a[0]);
+  ASSERT(4, g41[1].a[1]);
+
+  ASSERT(8, sizeof(g43));
+  ASSERT(0, strcmp(g43[0], "foo"));
+  ASSERT(0, strcmp(g43[1], "bar"));
+  ASSERT(0, strcmp(g44, "foo"));
+
+  ASSERT(1, g45 == 1.5);
+  ASSERT(1, g46 == 2.5);
+  ASSERT(1, g47 == 3.5);
+  ASSERT(2, g48);
+  ASSERT(1, g49);
+  ASSERT(0, strcmp(g50, "hi"));
+  ASSERT(12, sizeof(g51));
+  ASSERT(3, g51[2]);
+  ASSERT(255, g52);
+  ASSERT(1, g53 == 1099511627776);
+  ASSERT(3, g54);
+  ASSERT(12, sizeof(g55));
+  ASSERT(1, g55[1] == 2.5);
+  ASSERT(3, g55[2]);
+  ASSERT(2, g56[0][1]);
+  ASSERT(0, g56[0][2]);
+  ASSERT(4, g56[1][0]);
+  ASSERT(0, g56[1][2]);
+  ASSERT(97, g57);
+  ASSERT(3, g58);
+  ASSERT(1, g59.c);
+  ASSERT(2, g59.l);
+  ASSERT(3, g59.s);
+
+  ASSERT(3, g60[2]);
+  ASSERT(4, g60[3]);
+  ASSERT(1, g60[0]);
+  ASSERT(0, g60[4]);
+  ASSERT(20, sizeof(g61));
+  ASSERT(2, g61[1]);
+  ASSERT(5, g61[4]);
+  ASSERT(1, g62.a);
+  ASSERT(0, g62.b);
+  ASSERT(3, g62.c);
+  ASSERT(1, g63.b[0]);
+  ASSERT(2, g63.b[1]);
+  ASSERT(0, g63.b[2]);
+  ASSERT(0, g64.a[0]);
+  ASSERT(2, g64.a[1]);
+  ASSERT(3, g64.a[2]);
+  ASSERT(4, g64.b);
+
+  ASSERT(123, t1());
+  ASSERT(26, t2());
+  ASSERT(3, t3());
+  ASSERT(20, t4());
+  ASSERT(97, t5());
+  ASSERT(6, t6());
+  ASSERT(20, t7());
+  ASSERT(123, t8());
+  ASSERT(1, t9());
+  ASSERT(456, t10());
+  ASSERT(41, t11());
+  ASSERT(2045, t12());
+  ASSERT(73, t13());
+  ASSERT(123, t14());
+  ASSERT(105, t15());
+  ASSERT(2004, t16());
+  ASSERT(1, t17());
+  ASSERT(41, t18());
+  ASSERT(9, t19());
+  ASSERT(3, t20());
+  ASSERT(0, t21());
+  ASSERT(123, t22());
+  ASSERT(1450, t23());
+  ASSERT(11, t24());
+  ASSERT(5792, t25());
+  ASSERT(4350, t26());
+
+  printf("OK\n");
+  return 0;
+}
@@ -0,0 +1,29 @@
+#include "test.h"
+
+int strcmp(const char *, const char *);
+
+int main() {
+#line 500 "foo"
+  ASSERT(500, __LINE__);
+  ASSERT(0, strcmp(__FILE__, "foo"));
+
+#line 800 "bar"
+
+  ASSERT(801, __LINE__);
+  ASSERT(0, strcmp(__FILE__, "bar"));
+
+#line 1
+  ASSERT(1, __LINE__);
+  ASSERT(0, strcmp(__FILE__, "bar"));
+
+# 200 "xyz" 3
+  ASSERT(200, __LINE__);
+  ASSERT(0, strcmp(__FILE__, "xyz"));
+
+#define LINE_NUMBER 300
+#line LINE_NUMBER
+  ASSERT(300, __LINE__);
+
+  printf("OK\n");
+  return 0;
+}
@@ -0,0 +1,71 @@
+#include "test.h"
+
+int main() {
+  ASSERT(97, 'a');
+  ASSERT(10, '\n');
+  ASSERT(127, '\x7f');
+
+  ASSERT(511, 0777);
+  ASSERT(0, 0x0);
+  ASSERT(10, 0xa);
+  ASSERT(10, 0XA);
+  ASSERT(48879, 0xbeef);
+  ASSERT(48879, 0xBEEF);
+  ASSERT(48879, 0XBEEF);
+  ASSERT(0, 0b0);
+  ASSERT(1, 0b1);
+  ASSERT(47, 0b101111);
+  ASSERT(47, 0B101111);
+
+  ASSERT(4, sizeof(0));
+  ASSERT(8, sizeof(0L));
+  ASSERT(8, sizeof(0LU));
+  ASSERT(8, sizeof(0UL));
+  ASSERT(8, sizeof(0LL));
+  ASSERT(8, sizeof(0LLU));
+  ASSERT(8, sizeof(0Ull));
+  ASSERT(8, sizeof(0l));
+  ASSERT(8, sizeof(0ll));
+  ASSERT(8, sizeof(0x0L));
+  ASSERT(8, sizeof(0b0L));
+  ASSERT(4, sizeof(2147483647));
+  ASSERT(8, sizeof(2147483648));
+  ASSERT(-1, 0xffffffffffffffff);
+  ASSERT(8, sizeof(0xffffffffffffffff));
+  ASSERT(4, sizeof(4294967295U));
+  ASSERT(8, sizeof(4294967296U));
+
+  ASSERT(3, -1U / 1073741824);
+  ASSERT(3, -1UL / 4611686018427387904);
+  ASSERT(1, 0xffffffff / 2147483648);
+  ASSERT(1, 037777777777 / 2147483648);
+  ASSERT(1, 0xffffffffffffffff / 9223372036854775808);
+  ASSERT(1, 0xffffffffffffffff > 0);
+  ASSERT(0, -1 > 0);
+
+  ASSERT(-1, 18446744073709551615);
+
+  ASSERT(-1, 0xffffffffffffffff);
+  ASSERT(8, sizeof(0xffffffffffffffff));
+
+  ASSERT(-1, 01777777777777777777777);
+  ASSERT(8, sizeof(01777777777777777777777));
+
+  ASSERT(-1, 0b1111111111111111111111111111111111111111111111111111111111111111);
+  ASSERT(8, sizeof(0b1111111111111111111111111111111111111111111111111111111111111111));
+
+  ASSERT(8, sizeof(2147483648));
+  ASSERT(4, sizeof(2147483647));
+
+  ASSERT(8, sizeof(0x1ffffffff));
+  ASSERT(4, sizeof(0xffffffff));
+
+  ASSERT(8, sizeof(040000000000));
+  ASSERT(4, sizeof(037777777777));
+
+  ASSERT(8, sizeof(0b111111111111111111111111111111111));
+  ASSERT(4, sizeof(0b11111111111111111111111111111111));
+
+  printf("OK\n");
+  return 0;
+}
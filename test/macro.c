@@ -0,0 +1,246 @@
+#include "test.h"
+
+int strcmp(const char *, const char *);
+
+int M8(int x) { return x * 10; }
+int M9 = 2;
+
+#define ONE 1
+#define TWO ONE + ONE
+#define M1(x) x * 2
+#define M2(x, y) ((x) + (y))
+#define M3() 5
+#define M4(x) #x
+#define M5(x, y) x##y
+#define M6(x, ...) x + M7(__VA_ARGS__)
+#define M7(...) sum(0, __VA_ARGS__)
+#define M8(x) M8(x + 1)
+#define M9 M9 + 1
+#define M10(x) M11(x)
+#define M11(x) x + 10
+#define M12(f) f(3)
+#define M13(x) x##1
+#define M14(x, y) x##y##3
+#define M15(x) M4(x)
+#define M16(...) #__VA_ARGS__
+#define M17 M18(
+#define M18(x) x * 3
+
+int sum(int n, ...) { return n; }
+
+int foo1 = 7;
+
+int t1() { return M1(3); }
+
+int t2() { return M1(1 + 2); }
+
+int t3() { return M2(1, 2) * M2((3, 4), 5); }
+
+int t4() { return M3() + M3(); }
+
+int t5() { return !strcmp(M4(a + b), "a + b"); }
+
+int t6() { return !strcmp(M4(  "a\n"   'b'  ), "\"a\\n\" 'b'"); }
+
+int t7() {
+  int ab = 12;
+  return M5(a, b);
+}
+
+int t8() { return M5(12, 34) + M5(, 5) + M5(6, ); }
+
+int t9() { return M6(1, 2, 3); }
+
+int t10() { return !strcmp(M16(), ""); }
+
+int t11() {
+  int x = 1;
+  return M8(x);
+}
+
+int t12() { return M9; }
+
+int t13() { return M10(M1(2)); }
+
+int t14() { return M12(M1); }
+
+int t15() { return M13(foo); }
+
+int t16() { return M14(1, 2); }
+
+int t17() { return !strcmp(M15(ONE), "1") + !strcmp(M4(ONE), "ONE") * 10; }
+
+int t18() { return !strcmp(M16(a, b,c), "a, b,c"); }
+
+int t19() { return M17 2); }
+
+int t20() {
+  return M2(1,
+            2) +
+         M1
+         (4);
+}
+
+int t21() { return TWO * M1(TWO); }
+
+int t22() {
+  int m = 0;
+#if 0
+  m = 1;
+#elif 1 + 1 == 2
+  m = 2;
+#else
+  m = 3;
+#endif
+  return m;
+}
+
+int t23() {
+  int m = 0;
+#if 1
+#if 0
+  m = 1;
+#else
+  m = 2;
+#if 0
+#elif 1
+  m = m * 10 + 3;
+#endif
+#endif
+#else
+  m = 4;
+#endif
+  return m;
+}
+
+int t24() {
+  int m = 0;
+#ifdef ONE
+  m += 1;
+#endif
+#ifndef ONE
+  m += 10;
+#endif
+#if defined(ONE) && defined TWO && !defined(NOPE)
+  m += 100;
+#endif
+#ifdef NOPE
+  m += 1000;
+#else
+  m += 2000;
+#endif
+  return m;
+}
+
+int t25() {
+  int m = 0;
+#if -1 < 0u
+  m += 1;
+#endif
+#if -1 < 0
+  m += 10;
+#endif
+#if (1 ? -1 : 0u) > 0
+  m += 100;
+#endif
+#if 0xffffffff > 0 && 1 << 40 == 0x10000000000
+  m += 1000;
+#endif
+#if (-16 >> 2) == -4 && 7 / 2 * 2 + 7 % 2 == 7 && ~0 == -1 && (3 ^ 5 | 8) == 14
+  m += 10000;
+#endif
+#if 0 && 1 / 0 || UNDEFINED_NAME
+  m += 100000;
+#endif
+#if 'a' == 97 && M2(1, 2) == 3
+  m += 1000000;
+#endif
+  return m;
+}
+
+#define M19 3
+#undef M19
+#define M20
+#define M21(x) defined(x)
+
+int t26() {
+  int m = 0;
+#if M19
+  m = 1;
+#elif defined(M20) && defined M21 && !M19
+  m = 2;
+#endif
+  return m;
+}
+
+int t27() {
+  return __STDC__ + (__STDC_VERSION__ == 201112L) * 10 + (sizeof(int) == __SIZEOF_INT__) * 100 +
+         (sizeof(long) == __SIZEOF_LONG__) * 1000 + (sizeof(void *) == __SIZEOF_POINTER__) * 10000;
+}
+
+#define M22 __LINE__
+
+int t28() {
+  int a = __LINE__;
+  int b = M22;
+  return b - a;
+}
+
+int t29() { return !strcmp(__FILE__, "test/macro.c"); }
+
+int t30() {
+  int a = __COUNTER__;
+  int b = __COUNTER__;
+  return b - a;
+}
+
+int t31() { return sizeof(__DATE__) * 100 + sizeof(__TIME__); }
+
+#define hash_hash # ## #
+#define mkstr(a) # a
+#define in_between(a) mkstr(a)
+#define join(c, d) in_between(c hash_hash d)
+#define M23 1 ## 2
+
+int t32() {
+  char p[] = join(x, y);
+  return !strcmp(p, "x ## y") * 100 + M23;
+}
+
+int main() {
+  ASSERT(6, t1());
+  ASSERT(5, t2());
+  ASSERT(27, t3());
+  ASSERT(10, t4());
+  ASSERT(1, t5());
+  ASSERT(1, t6());
+  ASSERT(12, t7());
+  ASSERT(1245, t8());
+  ASSERT(1, t9());
+  ASSERT(1, t10());
+  ASSERT(20, t11());
+  ASSERT(3, t12());
+  ASSERT(14, t13());
+  ASSERT(6, t14());
+  ASSERT(7, t15());
+  ASSERT(123, t16());
+  ASSERT(11, t17());
+  ASSERT(1, t18());
+  ASSERT(6, t19());
+  ASSERT(11, t20());
+  ASSERT(4, t21());
+  ASSERT(2, t22());
+  ASSERT(23, t23());
+  ASSERT(2101, t24());
+  ASSERT(1011110, t25());
+  ASSERT(2, t26());
+  ASSERT(11111, t27());
+  ASSERT(1, t28());
+  ASSERT(1, t29());
+  ASSERT(1, t30());
+  ASSERT(1209, t31());
+  ASSERT(112, t32());
+
+  printf("OK\n");
+  return 0;
+}
@@ -2,16 +2,29 @@
 
 int main() {
   ASSERT(1, sizeof(char));
-  // ASSERT(2, sizeof(short));
-  // ASSERT(2, sizeof(short int));
-  // ASSERT(2, sizeof(int short));
+  ASSERT(2, sizeof(short));
+  ASSERT(2, sizeof(short int));
+  ASSERT(2, sizeof(int short));
   ASSERT(4, sizeof(int));
-  // ASSERT(8, sizeof(long));
-  // ASSERT(8, sizeof(long int));
-  // ASSERT(8, sizeof(long int));
+  ASSERT(8, sizeof(long));
+  ASSERT(8, sizeof(long int));
+  ASSERT(8, sizeof(int long));
+  ASSERT(8, sizeof(long long));
+  ASSERT(8, sizeof(long long int));
+  ASSERT(1, sizeof(signed char));
+  ASSERT(1, sizeof(unsigned char));
+  ASSERT(2, sizeof(unsigned short));
+  ASSERT(2, sizeof(short unsigned int));
+  ASSERT(4, sizeof(signed));
+  ASSERT(4, sizeof(unsigned));
+  ASSERT(4, sizeof(unsigned int));
+  ASSERT(8, sizeof(unsigned long));
+  ASSERT(8, sizeof(long unsigned int));
+  ASSERT(8, sizeof(unsigned long long));
+  ASSERT(8, sizeof(signed long long int));
   ASSERT(8, sizeof(char *));
   ASSERT(8, sizeof(int *));
-  // ASSERT(8, sizeof(long *));
+  ASSERT(8, sizeof(long *));
   ASSERT(8, sizeof(int **));
   ASSERT(8, sizeof(int (*)[8]));
   ASSERT(32, sizeof(int *[4]));
@@ -22,6 +35,13 @@ int main() {
            int b;
          }));
 
+  ASSERT(4, sizeof((unsigned char)1 + (unsigned char)1));
+  ASSERT(4, sizeof((unsigned short)1 + (short)1));
+  ASSERT(4, sizeof(1 + 1U));
+  ASSERT(8, sizeof(1U + 1L));
+  ASSERT(8, sizeof(1L + 1UL));
+  ASSERT(1, sizeof(char) - 2 > 0);
+
   printf("OK\n");
   return 0;
 }
\ No newline at end of file
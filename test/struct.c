@@ -266,6 +266,117 @@ int t29() {
   x.a;
 }
 
+int t30() {
+  struct {
+    int a, b;
+  } x, y;
+  x.a = 3;
+  x.b = 5;
+  y = x;
+  return y.a * 10 + y.b;
+}
+
+int t31() {
+  struct t {
+    char a, b;
+  } x;
+  x.a = 1;
+  x.b = 2;
+  struct t y = x;
+  return y.a + y.b;
+}
+
+int t32() {
+  struct t {
+    long a;
+    char b[13];
+  } x, y, z;
+  x.a = 100;
+  x.b[12] = 7;
+  z = y = x;
+  return z.a + z.b[12] + y.b[12];
+}
+
+int t33() {
+  struct t {
+    char c[3000];
+  } x, y;
+  x.c[0] = 1;
+  x.c[2047] = 2;
+  x.c[2999] = 3;
+  y = x;
+  return y.c[0] + y.c[2047] + y.c[2999];
+}
+
+int t34() {
+  union {
+    int a;
+    char b[4];
+  } x, y;
+  x.a = 0x01020304;
+  y = x;
+  return y.b[0] + y.b[3];
+}
+
+int t35() {
+  struct t {
+    int a;
+  } x, *p;
+  struct t arr[2];
+  x.a = 9;
+  p = &arr[1];
+  *p = x;
+  arr[0] = arr[1];
+  return arr[0].a;
+}
+
+int t36() {
+  struct {
+    short a;
+    struct {
+      char c;
+      int d;
+    } in;
+  } x, y;
+  x.in.d = 42;
+  y.in = x.in;
+  return y.in.d;
+}
+
+int t37() {
+  struct t {
+    char a;
+  } x;
+  {
+    struct t {
+      long a, b;
+    } y;
+    x.a = sizeof(y);
+  }
+  struct t z;
+  z = x;
+  return z.a + sizeof(z);
+}
+
+int t38() {
+  struct {
+    int x, y;
+  } a, b;
+  b.x = 3;
+  b.y = 4;
+  return (a = b).y * 10 + a.x;
+}
+
+int t39() {
+  struct {
+    long a[300];
+  } x, y, *p = &x, *q = &y;
+  for (int i = 0; i < 300; i++)
+    y.a[i] = i;
+  *p = *q;
+  return x.a[0] + x.a[255] + x.a[256] + x.a[299];
+}
+
 int main() {
   ASSERT(1, t1());
   ASSERT(2, t2());
@@ -297,6 +408,16 @@ int main() {
 
   ASSERT(3, t28());
   ASSERT(3, t29());
+  ASSERT(35, t30());
+  ASSERT(3, t31());
+  ASSERT(114, t32());
+  ASSERT(6, t33());
+  ASSERT(5, t34());
+  ASSERT(9, t35());
+  ASSERT(42, t36());
+  ASSERT(17, t37());
+  ASSERT(43, t38());
+  ASSERT(810, t39());
 
   printf("OK\n");
 
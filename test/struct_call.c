@@ -0,0 +1,281 @@
+#include "test.h"
+
+typedef struct {
+  int quot;
+  int rem;
+} div_t;
+
+typedef struct {
+  long quot;
+  long rem;
+} ldiv_t;
+
+div_t div(int num, int denom);
+ldiv_t ldiv(long num, long denom);
+
+typedef struct {
+  char a;
+  short b;
+} Small;
+
+typedef struct {
+  long a;
+  int b;
+} Pair;
+
+typedef struct {
+  char c[3];
+} Odd;
+
+typedef struct {
+  float x;
+  float y;
+} Vec2;
+
+typedef struct {
+  double d;
+  int i;
+} Mixed;
+
+typedef struct {
+  long a;
+  long b;
+  long c;
+} Big;
+
+typedef union {
+  int i;
+  float f;
+} IntOrFloat;
+
+int small_sum(Small s) { return s.a + s.b; }
+
+long pair_sum(Pair p) { return p.a + p.b; }
+
+int odd_sum(Odd o) { return o.c[0] + o.c[1] + o.c[2]; }
+
+float vec2_dot(Vec2 a, Vec2 b) { return a.x * b.x + a.y * b.y; }
+
+double mixed_sum(Mixed m) { return m.d + m.i; }
+
+long big_sum(Big b) { return b.a + b.b + b.c; }
+
+int big_clobber(Big b) {
+  b.a = 100;
+  return b.a;
+}
+
+int union_int(IntOrFloat u) { return u.i; }
+
+long many_args(int a, Pair p, double d, Vec2 v, Big b, char c) {
+  return a + p.a + p.b + d + v.x + v.y + b.a + b.b + b.c + c;
+}
+
+Small make_small(char a, short b) {
+  Small s;
+  s.a = a;
+  s.b = b;
+  return s;
+}
+
+Odd make_odd(char a, char b, char c) {
+  Odd o;
+  o.c[0] = a;
+  o.c[1] = b;
+  o.c[2] = c;
+  return o;
+}
+
+Vec2 make_vec2(float x, float y) {
+  Vec2 v;
+  v.x = x;
+  v.y = y;
+  return v;
+}
+
+Mixed make_mixed(double d, int i) {
+  Mixed m;
+  m.d = d;
+  m.i = i;
+  return m;
+}
+
+Big make_big(long a, long b, long c) {
+  Big x;
+  x.a = a;
+  x.b = b;
+  x.c = c;
+  return x;
+}
+
+Big double_big(Big b) {
+  b.a = b.a * 2;
+  b.b = b.b * 2;
+  b.c = b.c * 2;
+  return b;
+}
+
+int t1() {
+  Small s;
+  s.a = 3;
+  s.b = 500;
+  return small_sum(s);
+}
+
+int t2() {
+  Pair p;
+  p.a = 4000000000;
+  p.b = 7;
+  return pair_sum(p) == 4000000007;
+}
+
+int t3() {
+  Odd o;
+  o.c[0] = 1;
+  o.c[1] = 2;
+  o.c[2] = 3;
+  return odd_sum(o);
+}
+
+int t4() {
+  Vec2 a;
+  Vec2 b;
+  a.x = 1.5;
+  a.y = 2;
+  b.x = 4;
+  b.y = 0.5;
+  return vec2_dot(a, b);
+}
+
+int t5() {
+  Mixed m;
+  m.d = 2.5;
+  m.i = 3;
+  return mixed_sum(m) * 2;
+}
+
+int t6() {
+  Big b;
+  b.a = 1;
+  b.b = 20;
+  b.c = 300;
+  return big_sum(b);
+}
+
+int t7() {
+  Big b;
+  b.a = 5;
+  big_clobber(b);
+  return b.a;
+}
+
+int t8() {
+  IntOrFloat u;
+  u.i = 42;
+  return union_int(u);
+}
+
+int t9() {
+  Pair p;
+  Vec2 v;
+  Big b;
+  p.a = 2;
+  p.b = 3;
+  v.x = 4;
+  v.y = 5;
+  b.a = 6;
+  b.b = 7;
+  b.c = 8;
+  return many_args(1, p, 1.5, v, b, 9);
+}
+
+int t10() {
+  Small s = make_small(-2, 300);
+  return s.a + s.b;
+}
+
+int t11() {
+  Odd o = make_odd(4, 5, 6);
+  return o.c[0] * 100 + o.c[1] * 10 + o.c[2];
+}
+
+int t12() {
+  Vec2 v = make_vec2(1.25, 2.5);
+  return v.x * 4 + v.y * 2;
+}
+
+int t13() {
+  Mixed m = make_mixed(0.5, 7);
+  return m.d * 10 + m.i;
+}
+
+int t14() {
+  Big b = make_big(1, 2, 3);
+  return b.a * 100 + b.b * 10 + b.c;
+}
+
+int t15() { return make_big(4, 5, 6).c; }
+
+int t16() { return make_vec2(3, 9).y; }
+
+int t17() {
+  Big b = double_big(make_big(1, 2, 3));
+  return b.a + b.b + b.c;
+}
+
+int t18() { return big_sum(double_big(make_big(10, 20, 30))); }
+
+int t19() {
+  div_t d = div(17, 5);
+  return d.quot * 10 + d.rem;
+}
+
+int t20() {
+  div_t d = div(-17, 5);
+  return d.quot * 10 + d.rem;
+}
+
+int t21() {
+  ldiv_t d = ldiv(100000000000, 7);
+  return d.quot == 14285714285 && d.rem == 5;
+}
+
+long split_pair(int a, int b, int c, int d, int e, int f, int g, Pair p,
+                Pair q) {
+  return a + b + c + d + e + f + g + p.a * 10 + p.b * 100 + q.a * 1000 +
+         q.b * 10000;
+}
+
+int t22() {
+  Pair p = {1, 2};
+  Pair q = {3, 4};
+  return split_pair(0, 0, 0, 0, 0, 0, 0, p, q) == 43210;
+}
+
+int main() {
+  ASSERT(503, t1());
+  ASSERT(1, t2());
+  ASSERT(6, t3());
+  ASSERT(7, t4());
+  ASSERT(11, t5());
+  ASSERT(321, t6());
+  ASSERT(5, t7());
+  ASSERT(42, t8());
+  ASSERT(46, t9());
+  ASSERT(298, t10());
+  ASSERT(456, t11());
+  ASSERT(10, t12());
+  ASSERT(12, t13());
+  ASSERT(123, t14());
+  ASSERT(6, t15());
+  ASSERT(9, t16());
+  ASSERT(12, t17());
+  ASSERT(120, t18());
+  ASSERT(32, t19());
+  ASSERT(-32, t20());
+  ASSERT(1, t21());
+  ASSERT(1, t22());
+
+  printf("OK\n");
+  return 0;
+}
@@ -0,0 +1,109 @@
+#include "test.h"
+
+typedef int MyInt, MyInt2[4];
+typedef int *IntPtr;
+typedef unsigned long size_t;
+typedef struct Node Node;
+
+struct Node {
+  int val;
+  Node *next;
+};
+
+typedef int (*BinFn)(int a, int b);
+
+int add(int a, int b) { return a + b; }
+
+MyInt twice(MyInt x) { return x * 2; }
+
+int sum2(MyInt a, MyInt b) { return a + b; }
+
+int t1() {
+  typedef int t;
+  t x = 1;
+  return x;
+}
+
+int t2() {
+  typedef struct {
+    int a;
+  } t;
+  t x;
+  x.a = 1;
+  return x.a;
+}
+
+int t3() {
+  typedef int t;
+  {
+    t t = 1;
+    return t;
+  }
+}
+
+int t4() {
+  typedef struct {
+    int a;
+  } t;
+  { typedef int t; }
+  t x;
+  x.a = 2;
+  return x.a;
+}
+
+int t6() {
+  MyInt x = 3;
+  return sizeof(x);
+}
+
+int t7() {
+  MyInt2 x;
+  return sizeof(x);
+}
+
+int t8() {
+  Node a;
+  Node b;
+  a.val = 3;
+  a.next = &b;
+  b.val = 4;
+  b.next = 0;
+  return a.val + a.next->val;
+}
+
+int t9() {
+  int x = 5;
+  IntPtr p = &x;
+  return *p;
+}
+
+int t10() {
+  typedef char T;
+  int r = sizeof(T);
+  {
+    typedef long T;
+    r = r * 10 + sizeof(T);
+  }
+  return r * 10 + sizeof(T);
+}
+
+int main() {
+  ASSERT(1, t1());
+  ASSERT(1, t2());
+  ASSERT(1, t3());
+  ASSERT(2, t4());
+  ASSERT(4, t6());
+  ASSERT(16, t7());
+  ASSERT(7, t8());
+  ASSERT(5, t9());
+  ASSERT(181, t10());
+  ASSERT(8, sizeof(size_t));
+  ASSERT(8, sizeof(IntPtr));
+  ASSERT(16, sizeof(Node));
+  ASSERT(10, twice(5));
+  ASSERT(7, sum2(3, 4));
+  ASSERT(8, sizeof(BinFn));
+
+  printf("OK\n");
+  return 0;
+}
@@ -0,0 +1,121 @@
+#include "test.h"
+
+typedef struct Expr Expr;
+
+enum Kind { NUM, ADD };
+
+struct Expr {
+  enum Kind kind;
+  union {
+    long num;
+    struct {
+      Expr *lhs;
+      Expr *rhs;
+    } bin;
+  } as;
+};
+
+long eval(Expr *e) {
+  if (e->kind == NUM)
+    return e->as.num;
+  return eval(e->as.bin.lhs) + eval(e->as.bin.rhs);
+}
+
+int t1() {
+  union {
+    int a;
+    char b[6];
+  } x;
+  return sizeof(x);
+}
+
+int t2() {
+  union {
+    int a;
+    char b[4];
+  } x;
+  x.a = 515;
+  return x.b[0];
+}
+
+int t3() {
+  union {
+    int a;
+    char b[4];
+  } x;
+  x.a = 515;
+  return x.b[1];
+}
+
+int t4() {
+  union {
+    int a;
+    char b[4];
+  } x;
+  x.b[0] = 1;
+  x.b[1] = 0;
+  x.b[2] = 0;
+  x.b[3] = 0;
+  return x.a;
+}
+
+int t5() {
+  union U {
+    char c;
+    long l;
+  };
+  union U u;
+  union U *p = &u;
+  p->l = 0;
+  p->c = 7;
+  return u.l;
+}
+
+int t6() {
+  union {
+    char a;
+    short b;
+    double c;
+  } x;
+  return sizeof(x);
+}
+
+int t7() {
+  struct {
+    char c;
+    union {
+      char a[3];
+      short b;
+    } u;
+  } x;
+  return sizeof(x);
+}
+
+int t8() {
+  Expr one;
+  Expr two;
+  Expr sum;
+  one.kind = NUM;
+  one.as.num = 1;
+  two.kind = NUM;
+  two.as.num = 2;
+  sum.kind = ADD;
+  sum.as.bin.lhs = &one;
+  sum.as.bin.rhs = &two;
+  return eval(&sum);
+}
+
+int main() {
+  ASSERT(8, t1());
+  ASSERT(3, t2());
+  ASSERT(2, t3());
+  ASSERT(1, t4());
+  ASSERT(7, t5());
+  ASSERT(8, t6());
+  ASSERT(6, t7());
+  ASSERT(3, t8());
+  ASSERT(24, sizeof(Expr));
+
+  printf("OK\n");
+  return 0;
+}
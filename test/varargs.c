@@ -0,0 +1,202 @@
+#include "test.h"
+#include <stdarg.h>
+
+int sprintf(char *, const char *, ...);
+int vsprintf(char *, const char *, va_list);
+int strcmp(const char *, const char *);
+
+typedef struct {
+  int a;
+  char b;
+} Small;
+
+typedef struct {
+  long a;
+  long b;
+  long c;
+} Big;
+
+int sum_int(int n, ...) {
+  va_list ap;
+  va_start(ap, n);
+
+  int sum = 0;
+  for (int i = 0; i < n; i++)
+    sum = sum + va_arg(ap, int);
+
+  va_end(ap);
+  return sum;
+}
+
+long sum_long(int n, ...) {
+  va_list ap;
+  va_start(ap, n);
+
+  long sum = 0;
+  for (int i = 0; i < n; i++)
+    sum = sum + va_arg(ap, long);
+
+  va_end(ap);
+  return sum;
+}
+
+double sum_double(double scale, int n, ...) {
+  va_list ap;
+  va_start(ap, n);
+
+  double sum = 0;
+  for (int i = 0; i < n; i++)
+    sum = sum + va_arg(ap, double);
+
+  va_end(ap);
+  return sum * scale;
+}
+
+int sum_twice(int n, ...) {
+  va_list ap;
+  va_list ap2;
+  va_start(ap, n);
+  va_copy(ap2, ap);
+
+  int sum = 0;
+  for (int i = 0; i < n; i++)
+    sum = sum + va_arg(ap, int) * 10 + va_arg(ap2, int);
+
+  va_end(ap);
+  va_end(ap2);
+  return sum;
+}
+
+int struct_args(int n, ...) {
+  va_list ap;
+  va_start(ap, n);
+  Small s = va_arg(ap, Small);
+  Big b = va_arg(ap, Big);
+  int last = va_arg(ap, int);
+  va_end(ap);
+  return s.a + s.b + b.a + b.b + b.c + last;
+}
+
+Big big_with_varargs(Big b, ...) {
+  va_list ap;
+  va_start(ap, b);
+  b.a = b.a + va_arg(ap, int);
+  va_end(ap);
+  return b;
+}
+
+char *fmt(char *buf, const char *fmt, ...) {
+  va_list ap;
+  va_start(ap, fmt);
+  vsprintf(buf, fmt, ap);
+  va_end(ap);
+  return buf;
+}
+
+int late_struct_args(int a, int b, int c, int d, int e, int f, int g, ...) {
+  va_list ap;
+  va_start(ap, g);
+  Small s = va_arg(ap, Small);
+  Big big = va_arg(ap, Big);
+  int last = va_arg(ap, int);
+  va_end(ap);
+  return a + b + c + d + e + f + g + s.a + s.b + big.a + big.b + big.c + last;
+}
+
+long many_named(long a, long b, long c, long d, long e, long f, long g,
+                long h, long i, ...) {
+  va_list ap;
+  va_start(ap, i);
+  long j = va_arg(ap, long);
+  long k = va_arg(ap, long);
+  va_end(ap);
+  return a + b + c + d + e + f + g + h + i * 10 + j * 100 + k * 1000;
+}
+
+int t1() { return sum_int(0); }
+
+int t2() { return sum_int(3, 1, 2, 3); }
+
+int t3() { return sum_int(7, 1, 2, 3, 4, 5, 6, 7); }
+
+int t4() { return sum_long(2, 4000000000, 5000000000) == 9000000000; }
+
+int t5() { return sum_double(2.0, 3, 1.5, 2.25, 0.25); }
+
+int t6() { return sum_double(1.0, 2, 1.5f, 2.5f); }
+
+int t7() { return sum_twice(3, 1, 2, 3); }
+
+int t8() {
+  Small s;
+  Big b;
+  s.a = 1;
+  s.b = 2;
+  b.a = 10;
+  b.b = 20;
+  b.c = 30;
+  return struct_args(3, s, b, 100);
+}
+
+int t9() {
+  Big b;
+  b.a = 1;
+  b.b = 2;
+  b.c = 3;
+  return big_with_varargs(b, 40).a;
+}
+
+int t10() {
+  char buf[100];
+  fmt(buf, "%d %s %ld", 42, "abc", 10000000000);
+  return strcmp(buf, "42 abc 10000000000");
+}
+
+int t11() {
+  char buf[100];
+  fmt(buf, "%.2f %c", 3.14159, 'x');
+  return strcmp(buf, "3.14 x");
+}
+
+int t12() {
+  char buf[100];
+  sprintf(buf, "%d %d %d %d %d %d %d %d %.1f %d", 1, 2, 3, 4, 5, 6, 7, 8, 9.5,
+          10);
+  return strcmp(buf, "1 2 3 4 5 6 7 8 9.5 10");
+}
+
+int t13() { return sum_int(12, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12); }
+
+int t14() {
+  return sum_double(2.0, 10, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.5);
+}
+
+int t15() {
+  Small s = {1, 2};
+  Big b = {10, 20, 30};
+  return late_struct_args(0, 0, 0, 0, 0, 0, 0, s, b, 100);
+}
+
+int t16() { return many_named(1, 1, 1, 1, 1, 1, 1, 1, 2, 3, 4); }
+
+int main() {
+  ASSERT(0, t1());
+  ASSERT(6, t2());
+  ASSERT(28, t3());
+  ASSERT(1, t4());
+  ASSERT(8, t5());
+  ASSERT(4, t6());
+  ASSERT(66, t7());
+  ASSERT(163, t8());
+  ASSERT(41, t9());
+  ASSERT(0, t10());
+  ASSERT(0, t11());
+  ASSERT(0, t12());
+  ASSERT(78, t13());
+  ASSERT(111, t14());
+  ASSERT(163, t15());
+  ASSERT(4328, t16());
+
+  printf("OK\n");
+  return 0;
+}
@@ -1,5 +1,22 @@
 #include "test.h"
 
+int t43(int x) {
+  static int end, begin, memzero;
+  if (x)
+    end++;
+  else
+    end--;
+  if (x)
+    end++;
+  if (x)
+    end++;
+  if (x)
+    end++;
+  for (int i = 0; i < 2; i++)
+    begin++;
+  return end * 100 + begin * 10 + memzero;
+}
+
 int g1, g2[4];
 
 int t1() {
@@ -171,6 +188,66 @@ int t33() {
   }
   return x;
 }
+int t34() {
+  short x;
+  return sizeof(x);
+}
+int t35() {
+  long x;
+  return sizeof(x);
+}
+int t36() {
+  short x = 3;
+  short y = 5;
+  return x + y;
+}
+int t37() {
+  long x = 3;
+  long y = 5;
+  return y - x;
+}
+int t38() {
+  short x[3];
+  x[0] = 1;
+  x[1] = -2;
+  x[2] = 3;
+  return x[0] + x[1] + x[2];
+}
+int t39() {
+  long x[3];
+  x[0] = 1;
+  x[1] = 2;
+  x[2] = 3;
+  return x[2];
+}
+int counter() {
+  static int i;
+  static int j = 1 + 1;
+  return i++ + j++;
+}
+int counter2() {
+  static int i = 10;
+  return i++;
+}
+static int g3 = 3;
+int t40() {
+  static char s[] = "abc";
+  return sizeof(s) + s[1];
+}
+int t41() {
+  static int x = 5;
+  static int *p = &x;
+  *p = *p + 1;
+  return x;
+}
+int t42() {
+  static struct {
+    int a;
+    int b;
+  } s = {1, 2};
+  s.a = s.a * 10;
+  return s.a + s.b;
+}
 
 int main() {
   ASSERT(3, t1());
@@ -217,6 +294,28 @@ int main() {
   ASSERT(2, t32());
   ASSERT(3, t33());
 
+  ASSERT(2, t34());
+  ASSERT(8, t35());
+  ASSERT(8, t36());
+  ASSERT(2, t37());
+  ASSERT(2, t38());
+  ASSERT(3, t39());
+
+  ASSERT(2, counter());
+  ASSERT(4, counter());
+  ASSERT(6, counter());
+  ASSERT(10, counter2());
+  ASSERT(11, counter2());
+  ASSERT(8, counter());
+  ASSERT(3, g3);
+  ASSERT(102, t40());
+  ASSERT(6, t41());
+  ASSERT(7, t41());
+  ASSERT(12, t42());
+  ASSERT(102, t42());
+  ASSERT(420, t43(1));
+  ASSERT(340, t43(0));
+
   // TODO: block scope
   {
     void *x;